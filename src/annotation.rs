@@ -0,0 +1,100 @@
+use eframe::egui::Color32;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+
+pub const ANNOTATION_COLOR: Color32 = Color32::from_rgb(255, 200, 0);
+
+#[derive(Clone)]
+pub struct Annotation {
+    pub name: String,
+    /// 区间的起止样本, 左闭右开
+    pub start: usize,
+    pub end: usize,
+    pub color: Color32,
+    pub comment: String,
+}
+
+/// 标注保存在信号文件旁边, 例如 `capture.cf32` 对应 `capture.cf32.annotations`
+pub fn sidecar_path(signal_path: &str) -> String {
+    format!("{}.annotations", signal_path)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('t') => ret.push('\t'),
+                Some('n') => ret.push('\n'),
+                Some(c) => ret.push(c),
+                None => {}
+            }
+        } else {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+/// 每行一个标注, 以制表符分隔: start end #rrggbb name comment
+pub fn save_annotations(path: &str, annotations: &[Annotation]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "# esig annotations")?;
+    for a in annotations {
+        writeln!(
+            file,
+            "{}\t{}\t#{:02x}{:02x}{:02x}\t{}\t{}",
+            a.start,
+            a.end,
+            a.color.r(),
+            a.color.g(),
+            a.color.b(),
+            escape(&a.name),
+            escape(&a.comment)
+        )?;
+    }
+    Ok(())
+}
+
+pub fn load_annotations(path: &str) -> io::Result<Vec<Annotation>> {
+    let file = File::open(path)?;
+    let mut annotations = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.splitn(5, '\t').collect();
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid line: {}", line),
+            )
+        };
+        if fields.len() < 4 {
+            return Err(invalid());
+        }
+        let start = fields[0].parse().map_err(|_| invalid())?;
+        let end = fields[1].parse().map_err(|_| invalid())?;
+        let color = fields[2]
+            .strip_prefix('#')
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .map(|rgb| Color32::from_rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+            .unwrap_or(ANNOTATION_COLOR);
+        annotations.push(Annotation {
+            name: unescape(fields[3]),
+            start,
+            end,
+            color,
+            comment: fields.get(4).map(|s| unescape(s)).unwrap_or_default(),
+        });
+    }
+    Ok(annotations)
+}
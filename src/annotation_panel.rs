@@ -0,0 +1,132 @@
+use crate::annotation::{save_annotations, sidecar_path, Annotation, ANNOTATION_COLOR};
+use crate::signal_plot::SignalPlot;
+use crate::utils::human_readable_time;
+use eframe::egui::{self, Grid, Widget};
+
+#[derive(Default)]
+pub struct AnnotationPanel {
+    selected: Option<usize>,
+}
+
+impl AnnotationPanel {
+    /// 标注有改动时自动保存到旁边的文件, 保存失败时返回错误信息.
+    /// `signal_path` 为空表示信号不是直接从文件读入的, 不保存
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        signal_plot: &mut SignalPlot,
+        signal_path: &str,
+    ) -> Result<(), String> {
+        let mut changed = false;
+        let mut jump = None;
+        let sample_rate = signal_plot.sample_rate() as f64;
+        let measure_range = signal_plot.measure_range();
+        egui::SidePanel::right("annotations")
+            .resizable(true)
+            .default_width(250.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Annotations");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui
+                            .add_enabled(measure_range.is_some(), egui::Button::new("Add"))
+                            .on_hover_text("Add the selected region (R + Drag) as an annotation")
+                            .clicked()
+                        {
+                            let range = measure_range.clone().unwrap();
+                            let annotations = signal_plot.annotations_mut();
+                            annotations.push(Annotation {
+                                name: format!("Region {}", annotations.len() + 1),
+                                start: range.start,
+                                end: range.end,
+                                color: ANNOTATION_COLOR,
+                                comment: String::new(),
+                            });
+                            self.selected = Some(annotations.len() - 1);
+                            changed = true;
+                        }
+                    });
+                });
+                ui.separator();
+                let annotations = signal_plot.annotations_mut();
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height() / 2.)
+                    .show(ui, |ui| {
+                        for (i, annotation) in annotations.iter().enumerate() {
+                            let text = egui::RichText::new(format!(
+                                "{}  ({})",
+                                annotation.name,
+                                human_readable_time(annotation.start as f64 / sample_rate)
+                            ))
+                            .color(annotation.color);
+                            let response = ui.selectable_label(self.selected == Some(i), text);
+                            if response.clicked() {
+                                self.selected = Some(i);
+                            }
+                            if response.double_clicked() {
+                                jump = Some(i);
+                            }
+                        }
+                    });
+                let Some(i) = self.selected.filter(|&i| i < annotations.len()) else {
+                    return;
+                };
+                ui.separator();
+                let mut delete = false;
+                let annotation = &mut annotations[i];
+                Grid::new("annotation-editor")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Name");
+                        changed |= egui::TextEdit::singleline(&mut annotation.name)
+                            .ui(ui)
+                            .changed();
+                        ui.end_row();
+                        ui.label("Color");
+                        changed |= ui.color_edit_button_srgba(&mut annotation.color).changed();
+                        ui.end_row();
+                        ui.label("Start");
+                        changed |= egui::DragValue::new(&mut annotation.start).ui(ui).changed();
+                        ui.end_row();
+                        ui.label("End");
+                        changed |= egui::DragValue::new(&mut annotation.end).ui(ui).changed();
+                        ui.end_row();
+                    });
+                ui.label("Comment");
+                changed |= egui::TextEdit::multiline(&mut annotation.comment)
+                    .desired_width(f32::INFINITY)
+                    .ui(ui)
+                    .changed();
+                ui.horizontal(|ui| {
+                    if ui.button("Go to").clicked() {
+                        jump = Some(i);
+                    }
+                    if ui.button("Delete").clicked() {
+                        delete = true;
+                    }
+                });
+                if delete {
+                    annotations.remove(i);
+                    self.selected = None;
+                    changed = true;
+                }
+            });
+        if let Some(i) = jump {
+            let annotation = &signal_plot.annotations()[i];
+            // 两边各留出一部分空白
+            let margin = annotation.end.saturating_sub(annotation.start).max(1) as f64 * 0.1;
+            let (start, end) = (annotation.start as f64, annotation.end as f64);
+            signal_plot.set_x_range(start - margin, end + margin);
+        }
+        if changed && !signal_path.is_empty() {
+            save_annotations(&sidecar_path(signal_path), signal_plot.annotations())
+                .map_err(|err| format!("Failed to save annotations: {}", err))?;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.selected = None;
+    }
+}
@@ -1,12 +1,26 @@
+use crate::annotation::{load_annotations, sidecar_path};
+use crate::annotation_panel::AnnotationPanel;
+use crate::bookmark_dialog::BookmarkDialog;
+use crate::burst_dialog::BurstDialog;
+use crate::cursor_dialog::CursorDialog;
+use crate::decoder_dialog::DecoderDialog;
+use crate::demod_dialog::DemodDialog;
+use crate::digital_dialog::DigitalDialog;
 use crate::export_dialog::ExportDialog;
-use crate::fft::compute_psd;
+use crate::filter_dialog::FilterDialog;
+use crate::goto_dialog::GotoDialog;
+use crate::histogram_dialog::HistogramDialog;
 use crate::menubar::{MenuBar, MenuItem};
 use crate::open_dialog::OpenDialog;
+use crate::playback_dialog::PlaybackDialog;
 use crate::psd_dialog::PsdDialog;
-use crate::signal_plot::{Signal, SignalPlot};
+use crate::resample_dialog::ResampleDialog;
+use crate::search::Direction;
+use crate::search_dialog::SearchDialog;
+use crate::signal_plot::{PlotStyle, Signal, SignalPlot};
+use crate::stats_dialog::StatsDialog;
 use crate::utils::{human_readable_freq, human_readable_time};
-use eframe::egui::{self, Key, Modifiers};
-use rustfft::num_complex::Complex;
+use eframe::egui::{self, Key, Modifiers, Widget};
 use std::fs::File;
 use std::io::Write;
 use std::slice;
@@ -17,20 +31,70 @@ enum MenuAction {
     Export,
     Quit,
     Reset,
-    Return,
+    Back,
+    Forward,
+    Bookmarks,
+    Goto,
+    Find,
+    FindNext,
+    FindPrevious,
+    Bursts,
+    Filter,
+    Resample,
+    Demod,
+    DigitalDemod,
+    Decoder,
+    Playback,
     Psd,
+    Histogram,
+    Cursors,
+    Stats,
+    Annotations,
     About,
     Mag,
+    Persistence,
+    ClearDerived,
+    LineStyle,
+    StepStyle,
+    StemStyle,
 }
 
 pub struct App {
     open_dialog: OpenDialog,
     menubar: MenuBar<MenuAction>,
     psd_dialog: PsdDialog,
+    histogram_dialog: HistogramDialog,
+    cursor_dialog: CursorDialog,
+    stats_dialog: StatsDialog,
+    annotation_panel: AnnotationPanel,
+    bookmark_dialog: BookmarkDialog,
+    goto_dialog: GotoDialog,
+    search_dialog: SearchDialog,
+    burst_dialog: BurstDialog,
+    filter_dialog: FilterDialog,
+    resample_dialog: ResampleDialog,
+    demod_dialog: DemodDialog,
+    digital_dialog: DigitalDialog,
+    decoder_dialog: DecoderDialog,
+    playback_dialog: PlaybackDialog,
     export_dialog: ExportDialog,
     export_dialog_visible: bool,
     open_dialog_visible: bool,
     psd_dialog_visible: bool,
+    histogram_dialog_visible: bool,
+    cursor_dialog_visible: bool,
+    stats_dialog_visible: bool,
+    annotation_panel_visible: bool,
+    bookmark_dialog_visible: bool,
+    goto_dialog_visible: bool,
+    search_dialog_visible: bool,
+    burst_dialog_visible: bool,
+    filter_dialog_visible: bool,
+    resample_dialog_visible: bool,
+    demod_dialog_visible: bool,
+    digital_dialog_visible: bool,
+    decoder_dialog_visible: bool,
+    playback_dialog_visible: bool,
     sample_rate: u32,
     psd_visiable: bool,
     signal_plot: SignalPlot,
@@ -45,10 +109,38 @@ impl Default for App {
             open_dialog: OpenDialog::default(),
             menubar: MenuBar::new(),
             psd_dialog: PsdDialog::default(),
+            histogram_dialog: HistogramDialog::default(),
+            cursor_dialog: CursorDialog::default(),
+            stats_dialog: StatsDialog::default(),
+            annotation_panel: AnnotationPanel::default(),
+            bookmark_dialog: BookmarkDialog::default(),
+            goto_dialog: GotoDialog::default(),
+            search_dialog: SearchDialog::default(),
+            burst_dialog: BurstDialog::default(),
+            filter_dialog: FilterDialog::default(),
+            resample_dialog: ResampleDialog::default(),
+            demod_dialog: DemodDialog::default(),
+            digital_dialog: DigitalDialog::default(),
+            decoder_dialog: DecoderDialog::default(),
+            playback_dialog: PlaybackDialog::default(),
             export_dialog: ExportDialog::default(),
             export_dialog_visible: false,
             open_dialog_visible: false,
             psd_dialog_visible: false,
+            histogram_dialog_visible: false,
+            cursor_dialog_visible: false,
+            stats_dialog_visible: false,
+            annotation_panel_visible: false,
+            bookmark_dialog_visible: false,
+            goto_dialog_visible: false,
+            search_dialog_visible: false,
+            burst_dialog_visible: false,
+            filter_dialog_visible: false,
+            resample_dialog_visible: false,
+            demod_dialog_visible: false,
+            digital_dialog_visible: false,
+            decoder_dialog_visible: false,
+            playback_dialog_visible: false,
             sample_rate: 1,
             psd_visiable: false,
             signal_plot: SignalPlot::new(),
@@ -116,10 +208,46 @@ impl App {
                     Key::R,
                 ),
                 MenuItem::single_with_shortcut(
-                    MenuAction::Return,
-                    "Return",
+                    MenuAction::Back,
+                    "Back",
                     Modifiers::COMMAND,
-                    Key::ArrowUp,
+                    Key::ArrowLeft,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Forward,
+                    "Forward",
+                    Modifiers::COMMAND,
+                    Key::ArrowRight,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Bookmarks,
+                    "Bookmarks",
+                    Modifiers::COMMAND,
+                    Key::B,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Goto,
+                    "Go to",
+                    Modifiers::COMMAND,
+                    Key::G,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Find,
+                    "Find Edge",
+                    Modifiers::COMMAND,
+                    Key::F,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::FindNext,
+                    "Find Next",
+                    Modifiers::NONE,
+                    Key::F3,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::FindPrevious,
+                    "Find Previous",
+                    Modifiers::SHIFT,
+                    Key::F3,
                 ),
                 MenuItem::single_with_shortcut(
                     MenuAction::Mag,
@@ -127,8 +255,64 @@ impl App {
                     Modifiers::COMMAND,
                     Key::M,
                 ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Cursors,
+                    "Toggle Cursors",
+                    Modifiers::COMMAND,
+                    Key::K,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Annotations,
+                    "Annotations",
+                    Modifiers::COMMAND,
+                    Key::L,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Persistence,
+                    "Toggle Persistence",
+                    Modifiers::COMMAND,
+                    Key::D,
+                ),
+                MenuItem::single(MenuAction::ClearDerived, "Clear Derived Traces"),
+                MenuItem::new(
+                    "Style",
+                    &[
+                        MenuItem::single(MenuAction::LineStyle, "Line"),
+                        MenuItem::single(MenuAction::StepStyle, "Step"),
+                        MenuItem::single(MenuAction::StemStyle, "Stem"),
+                    ],
+                ),
                 MenuItem::separator(),
                 MenuItem::single_with_shortcut(MenuAction::Psd, "PSD", Modifiers::COMMAND, Key::P),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Histogram,
+                    "Histogram",
+                    Modifiers::COMMAND,
+                    Key::H,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Stats,
+                    "Statistics",
+                    Modifiers::COMMAND,
+                    Key::T,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Bursts,
+                    "Bursts",
+                    Modifiers::COMMAND,
+                    Key::U,
+                ),
+                MenuItem::single_with_shortcut(
+                    MenuAction::Filter,
+                    "Filter",
+                    Modifiers::COMMAND,
+                    Key::I,
+                ),
+                MenuItem::single(MenuAction::Resample, "Shift / Resample"),
+                MenuItem::single(MenuAction::Demod, "Demodulate"),
+                MenuItem::single(MenuAction::DigitalDemod, "Digital Demodulate"),
+                MenuItem::single(MenuAction::Decoder, "Bit Decoder"),
+                MenuItem::single(MenuAction::Playback, "Play Selection"),
             ],
         ));
         self.menubar.add(MenuItem::new(
@@ -138,33 +322,52 @@ impl App {
     }
 
     pub fn psd(&mut self) -> Result<(), &str> {
-        if self.signal_plot.have_signal() {
+        if let Some(signal) = self.signal_plot.shared_signal() {
             self.psd_visiable = true;
-            let mut input = vec![];
-            match self.signal_plot.signal() {
-                Signal::Real(sig) => {
-                    for s in sig.get(self.signal_plot.range(), 1).iter() {
-                        input.push(Complex::new(*s as f64, 0.0));
-                    }
-                }
-                Signal::Complex(sig) => {
-                    for s in sig.get(self.signal_plot.range(), 1).iter() {
-                        input.push(Complex::new(s.re as f64, s.im as f64));
-                    }
-                }
-            }
-            if input.len() < 2048 {
+            let range = self.signal_plot.selected_range();
+            if range.len() < 2048 {
                 return Err("Signal length is too short!");
-            } else if input.len() > 50000 {
-                return Err("Signal length is too long, it may take a long time to compute!");
             }
-            let (freqs, psd) = compute_psd(&input, 1024, 0, self.sample_rate as f64);
-            self.psd_dialog.set_data(freqs, psd);
+            self.psd_dialog.set_signal(
+                signal,
+                range,
+                self.sample_rate as f64,
+                self.signal_plot.center_freq(),
+            );
             self.psd_dialog_visible = true;
         }
         Ok(())
     }
 
+    /// 换信号后丢弃所有和旧信号的样本位置有关的状态和后台任务
+    fn signal_replaced(&mut self) {
+        self.annotation_panel.clear();
+        self.bookmark_dialog.clear();
+        self.burst_dialog.clear();
+        self.filter_dialog.clear();
+        self.resample_dialog.clear();
+        self.psd_dialog.clear();
+        self.demod_dialog.clear();
+        self.playback_dialog.clear(&mut self.signal_plot);
+        self.digital_dialog.clear();
+        self.decoder_dialog.clear();
+        self.search_dialog.clear();
+        self.stats_dialog.clear();
+        self.histogram_dialog.clear();
+    }
+
+    pub fn histogram(&mut self) -> Result<(), &str> {
+        if let Some(signal) = self.signal_plot.shared_signal() {
+            let range = self.signal_plot.selected_range();
+            if range.len() < 2 {
+                return Err("Signal length is too short!");
+            }
+            self.histogram_dialog.set_signal(signal, range);
+            self.histogram_dialog_visible = true;
+        }
+        Ok(())
+    }
+
     pub fn export(&self, path: &str) {
         if self.signal_plot.have_signal() {
             let mut data = vec![];
@@ -207,8 +410,49 @@ impl App {
                 &MenuAction::Reset => {
                     self.signal_plot.reset_view();
                 }
-                &MenuAction::Return => {
-                    self.signal_plot.return_last_view();
+                &MenuAction::Back => {
+                    self.signal_plot.go_back();
+                }
+                &MenuAction::Forward => {
+                    self.signal_plot.go_forward();
+                }
+                &MenuAction::Goto if self.signal_plot.have_signal() => {
+                    self.goto_dialog_visible = true;
+                }
+                &MenuAction::Find if self.signal_plot.have_signal() => {
+                    self.search_dialog_visible = true;
+                }
+                &MenuAction::FindNext => {
+                    self.search_dialog
+                        .find(&mut self.signal_plot, Direction::Forward);
+                }
+                &MenuAction::FindPrevious => {
+                    self.search_dialog
+                        .find(&mut self.signal_plot, Direction::Backward);
+                }
+                &MenuAction::Bursts if self.signal_plot.have_signal() => {
+                    self.burst_dialog_visible = true;
+                }
+                &MenuAction::Filter => {
+                    self.filter_dialog_visible = !self.filter_dialog_visible;
+                }
+                &MenuAction::Resample if self.signal_plot.have_signal() => {
+                    self.resample_dialog_visible = true;
+                }
+                &MenuAction::Demod => {
+                    self.demod_dialog_visible = !self.demod_dialog_visible;
+                }
+                &MenuAction::DigitalDemod => {
+                    self.digital_dialog_visible = !self.digital_dialog_visible;
+                }
+                &MenuAction::Decoder => {
+                    self.decoder_dialog_visible = !self.decoder_dialog_visible;
+                }
+                &MenuAction::Playback => {
+                    self.playback_dialog_visible = !self.playback_dialog_visible;
+                }
+                &MenuAction::Bookmarks => {
+                    self.bookmark_dialog_visible = !self.bookmark_dialog_visible;
                 }
                 &MenuAction::Psd => {
                     if let Err(msg) = self.psd() {
@@ -216,9 +460,39 @@ impl App {
                         self.err_msg_visible = true;
                     }
                 }
+                &MenuAction::Histogram => {
+                    if let Err(msg) = self.histogram() {
+                        self.err_msg = Some(msg.to_owned());
+                        self.err_msg_visible = true;
+                    }
+                }
                 &MenuAction::Mag => {
                     self.signal_plot.toggle_magnitude();
                 }
+                &MenuAction::Cursors => {
+                    self.cursor_dialog_visible = !self.cursor_dialog_visible;
+                }
+                &MenuAction::Stats => {
+                    self.stats_dialog_visible = !self.stats_dialog_visible;
+                }
+                &MenuAction::Annotations => {
+                    self.annotation_panel_visible = !self.annotation_panel_visible;
+                }
+                &MenuAction::Persistence => {
+                    self.signal_plot.toggle_persistence();
+                }
+                &MenuAction::ClearDerived => {
+                    self.signal_plot.clear_derived();
+                }
+                &MenuAction::LineStyle => {
+                    self.signal_plot.set_plot_style(PlotStyle::Line);
+                }
+                &MenuAction::StepStyle => {
+                    self.signal_plot.set_plot_style(PlotStyle::Step);
+                }
+                &MenuAction::StemStyle => {
+                    self.signal_plot.set_plot_style(PlotStyle::Stem);
+                }
                 _ => {}
             }
         }
@@ -232,7 +506,33 @@ impl eframe::App for App {
         });
         egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.label(&self.signal_path);
+                if self.signal_path.is_empty() && self.signal_plot.have_signal() {
+                    ui.label("Resampled signal (annotations are not saved)");
+                } else {
+                    ui.label(&self.signal_path);
+                }
+                if self.signal_plot.have_signal() {
+                    // 可以直接输入可见范围 (单位为样本)
+                    let bounds = self.signal_plot.bounds();
+                    let mut x_min = *bounds.range_x().start();
+                    let mut x_max = *bounds.range_x().end();
+                    ui.separator();
+                    let mut changed = egui::DragValue::new(&mut x_min)
+                        .prefix("x: ")
+                        .max_decimals(1)
+                        .speed(0.)
+                        .ui(ui)
+                        .changed();
+                    ui.label("-");
+                    changed |= egui::DragValue::new(&mut x_max)
+                        .max_decimals(1)
+                        .speed(0.)
+                        .ui(ui)
+                        .changed();
+                    if changed && x_max > x_min {
+                        self.signal_plot.set_x_range(x_min, x_max);
+                    }
+                }
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.label(human_readable_time(self.signal_plot.window_time()));
                     ui.label(format!("{} samples", self.signal_plot.window_samples()));
@@ -242,6 +542,15 @@ impl eframe::App for App {
                 })
             });
         });
+        if self.annotation_panel_visible && self.signal_plot.have_signal() {
+            if let Err(msg) =
+                self.annotation_panel
+                    .show(ctx, &mut self.signal_plot, &self.signal_path)
+            {
+                self.err_msg = Some(msg);
+                self.err_msg_visible = true;
+            }
+        }
         egui::CentralPanel::default()
             .frame(
                 egui::Frame::default()
@@ -249,6 +558,9 @@ impl eframe::App for App {
                     .inner_margin(5.),
             )
             .show(ctx, |ui| {
+                self.signal_plot
+                    .set_cursors_visible(self.cursor_dialog_visible);
+                self.signal_plot.show_overview(ui);
                 self.signal_plot.show(ui);
 
                 if let Some((sig, sig_mag, path)) =
@@ -260,6 +572,21 @@ impl eframe::App for App {
                     self.signal_plot.reset_view();
                     self.sample_rate = self.open_dialog.sample_rate();
                     self.signal_plot.set_sample_rate(self.sample_rate);
+                    self.signal_plot
+                        .set_center_freq(self.open_dialog.center_freq());
+                    self.signal_replaced();
+                    let annotation_path = sidecar_path(&self.signal_path);
+                    if std::path::Path::new(&annotation_path).exists() {
+                        match load_annotations(&annotation_path) {
+                            Ok(annotations) => {
+                                *self.signal_plot.annotations_mut() = annotations;
+                            }
+                            Err(err) => {
+                                self.err_msg = Some(format!("Failed to load annotations: {}", err));
+                                self.err_msg_visible = true;
+                            }
+                        }
+                    }
                 }
 
                 let export_path = self
@@ -270,7 +597,95 @@ impl eframe::App for App {
                     self.export(export_path.unwrap().to_str().unwrap());
                 }
 
-                self.psd_dialog.show(ctx, &mut self.psd_dialog_visible);
+                if let Some(trace) = self.psd_dialog.show(ctx, &mut self.psd_dialog_visible) {
+                    self.signal_plot.add_derived(trace);
+                }
+                self.histogram_dialog
+                    .show(ctx, &mut self.histogram_dialog_visible);
+                self.cursor_dialog
+                    .show(ctx, &mut self.cursor_dialog_visible, &self.signal_plot);
+                self.stats_dialog
+                    .show(ctx, &mut self.stats_dialog_visible, &self.signal_plot);
+                self.bookmark_dialog.show(
+                    ctx,
+                    &mut self.bookmark_dialog_visible,
+                    &mut self.signal_plot,
+                );
+                self.bookmark_dialog.handle_keys(ctx, &mut self.signal_plot);
+                self.goto_dialog
+                    .show(ctx, &mut self.goto_dialog_visible, &mut self.signal_plot);
+                if let Err(msg) = self.burst_dialog.show(
+                    ctx,
+                    &mut self.burst_dialog_visible,
+                    &mut self.signal_plot,
+                ) {
+                    self.err_msg = Some(msg);
+                    self.err_msg_visible = true;
+                }
+                self.filter_dialog.show(
+                    ctx,
+                    &mut self.filter_dialog_visible,
+                    &mut self.signal_plot,
+                );
+                if let Err(msg) = self.demod_dialog.show(
+                    ctx,
+                    &mut self.demod_dialog_visible,
+                    &mut self.signal_plot,
+                ) {
+                    self.err_msg = Some(msg);
+                    self.err_msg_visible = true;
+                }
+                if let Err(msg) = self.digital_dialog.show(
+                    ctx,
+                    &mut self.digital_dialog_visible,
+                    &mut self.signal_plot,
+                ) {
+                    self.err_msg = Some(msg);
+                    self.err_msg_visible = true;
+                }
+                self.decoder_dialog.show(
+                    ctx,
+                    &mut self.decoder_dialog_visible,
+                    self.digital_dialog.bitstream(),
+                    &mut self.signal_plot,
+                );
+                if let Err(msg) = self.playback_dialog.show(
+                    ctx,
+                    &mut self.playback_dialog_visible,
+                    &mut self.signal_plot,
+                ) {
+                    self.err_msg = Some(msg);
+                    self.err_msg_visible = true;
+                }
+                if let Some(result) = self.resample_dialog.show(
+                    ctx,
+                    &mut self.resample_dialog_visible,
+                    &self.signal_plot,
+                ) {
+                    // 标注按新的采样率换算位置, 但不再对应原始文件, 之后的改动不保存
+                    let mut annotations = self.signal_plot.annotations().to_vec();
+                    for annotation in annotations.iter_mut() {
+                        annotation.start = (annotation.start as f64 * result.ratio) as usize;
+                        annotation.end = (annotation.end as f64 * result.ratio) as usize;
+                    }
+                    let center_freq = self.signal_plot.center_freq() - result.shift;
+                    self.signal_path.clear();
+                    self.signal_plot
+                        .set_signal(result.signal, result.signal_mag);
+                    self.signal_plot.reset_view();
+                    *self.signal_plot.annotations_mut() = annotations;
+                    self.sample_rate = result.sample_rate;
+                    self.signal_plot.set_sample_rate(self.sample_rate);
+                    self.signal_plot.set_center_freq(center_freq);
+                    self.signal_replaced();
+                }
+                if self.signal_plot.have_signal() {
+                    self.search_dialog.show(
+                        ctx,
+                        &mut self.search_dialog_visible,
+                        &mut self.signal_plot,
+                    );
+                }
 
                 if let Some(msg) = &self.err_msg {
                     egui::Window::new("Error")
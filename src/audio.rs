@@ -0,0 +1,170 @@
+use crate::demod::{demodulate, AnalogParams};
+use crate::dsp::{approximate_factors, resample};
+use crate::signal_plot::Signal;
+
+/// 没有声卡时渲染 WAV 文件使用的采样率
+pub const RENDER_RATE: u32 = 48000;
+
+/// 渲染好的音频和它在原始信号中的位置
+pub struct Audio {
+    pub samples: Vec<f32>,
+    pub rate: u32,
+    /// 第一个音频样本对应的原始样本位置
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    pub start: f64,
+    /// 每个音频样本对应多少个原始样本
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    pub step: f64,
+}
+
+impl Audio {
+    /// 第 `index` 个音频样本在原始信号中的位置, 用于绘制播放光标
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    pub fn sample_position(&self, index: usize) -> f64 {
+        self.start + index as f64 * self.step
+    }
+}
+
+/// 实信号直接播放, 复信号先解调. 结果重采样到 `rate` 并把峰值归一化到 0.9
+pub fn render_audio(
+    signal: &Signal,
+    range: std::ops::Range<usize>,
+    params: &AnalogParams,
+    sample_rate: u32,
+    rate: u32,
+) -> Option<Audio> {
+    let start = range.start as f64;
+    let (data, decimation) = match signal {
+        Signal::Real(sig) => (sig.get(range, 1).to_vec(), 1),
+        Signal::Complex(sig) => demodulate(sig.get(range, 1), params, sample_rate as f64),
+    };
+    // SSB 解调时已经抽取过
+    let sample_rate = (sample_rate as f64 / decimation as f64).round() as u32;
+    let (up, down) = approximate_factors(sample_rate, rate)?;
+    let mut samples = resample(&data, up, down);
+    // FM 解调输出的是频率, 先去掉直流再归一化
+    let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / samples.len().max(1) as f64;
+    let peak = samples
+        .iter()
+        .fold(0f32, |peak, &x| peak.max((x - mean as f32).abs()));
+    let scale = if peak > 0. { 0.9 / peak } else { 0. };
+    samples
+        .iter_mut()
+        .for_each(|x| *x = (*x - mean as f32) * scale);
+    Some(Audio {
+        samples,
+        rate,
+        start,
+        step: decimation as f64 * down as f64 / up as f64,
+    })
+}
+
+#[cfg(feature = "audio")]
+pub use output::AudioOutput;
+
+#[cfg(feature = "audio")]
+mod output {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, SampleFormat, SizedSample};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// 通过默认声卡播放单声道音频, 所有声道输出相同的内容
+    pub struct AudioOutput {
+        _stream: cpal::Stream,
+        position: Arc<AtomicUsize>,
+        len: usize,
+        /// 音频线程里发生的错误, 由界面取出显示
+        error: Arc<Mutex<Option<String>>>,
+    }
+
+    fn default_device() -> Result<cpal::Device, String> {
+        cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| "No audio output device".to_owned())
+    }
+
+    impl AudioOutput {
+        pub fn device_rate() -> Result<u32, String> {
+            let config = default_device()?
+                .default_output_config()
+                .map_err(|err| err.to_string())?;
+            Ok(config.sample_rate().0)
+        }
+
+        /// 按声卡默认配置的样本格式打开输出流, 音频样本转换成对应的格式
+        pub fn play(samples: Vec<f32>) -> Result<Self, String> {
+            let device = default_device()?;
+            let supported = device
+                .default_output_config()
+                .map_err(|err| err.to_string())?;
+            let format = supported.sample_format();
+            let config: cpal::StreamConfig = supported.into();
+            let len = samples.len();
+            let position = Arc::new(AtomicUsize::new(0));
+            let error = Arc::new(Mutex::new(None));
+            let (p, e) = (position.clone(), error.clone());
+            let stream = match format {
+                SampleFormat::F32 => build::<f32>(&device, &config, samples, p, e),
+                SampleFormat::F64 => build::<f64>(&device, &config, samples, p, e),
+                SampleFormat::I8 => build::<i8>(&device, &config, samples, p, e),
+                SampleFormat::I16 => build::<i16>(&device, &config, samples, p, e),
+                SampleFormat::I32 => build::<i32>(&device, &config, samples, p, e),
+                SampleFormat::U8 => build::<u8>(&device, &config, samples, p, e),
+                SampleFormat::U16 => build::<u16>(&device, &config, samples, p, e),
+                SampleFormat::U32 => build::<u32>(&device, &config, samples, p, e),
+                format => return Err(format!("Unsupported sample format {}", format)),
+            }
+            .map_err(|err| err.to_string())?;
+            stream.play().map_err(|err| err.to_string())?;
+            Ok(Self {
+                _stream: stream,
+                position,
+                len,
+                error,
+            })
+        }
+
+        pub fn position(&self) -> usize {
+            self.position.load(Ordering::Relaxed)
+        }
+
+        pub fn is_finished(&self) -> bool {
+            self.position() >= self.len
+        }
+
+        /// 取出音频线程报告的错误
+        pub fn take_error(&self) -> Option<String> {
+            self.error.lock().ok()?.take()
+        }
+    }
+
+    fn build<T: SizedSample + FromSample<f32>>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        samples: Vec<f32>,
+        position: Arc<AtomicUsize>,
+        error: Arc<Mutex<Option<String>>>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError> {
+        let channels = config.channels as usize;
+        let len = samples.len();
+        device.build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let mut index = position.load(Ordering::Relaxed);
+                for frame in data.chunks_mut(channels) {
+                    let sample = samples.get(index).copied().unwrap_or(0.);
+                    frame.fill(T::from_sample(sample));
+                    index = (index + 1).min(len);
+                }
+                position.store(index, Ordering::Relaxed);
+            },
+            move |err| {
+                if let Ok(mut error) = error.lock() {
+                    *error = Some(err.to_string());
+                }
+            },
+            None,
+        )
+    }
+}
@@ -0,0 +1,91 @@
+use crate::signal_plot::SignalPlot;
+use crate::utils::human_readable_time;
+use eframe::egui::{self, Key, Widget};
+use egui_plot::PlotBounds;
+
+const NUMBER_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+#[derive(Default)]
+pub struct BookmarkDialog {
+    name: String,
+    bookmarks: Vec<(String, PlotBounds)>,
+}
+
+impl BookmarkDialog {
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool, signal_plot: &mut SignalPlot) {
+        let sample_rate = signal_plot.sample_rate() as f64;
+        egui::Window::new("Bookmarks")
+            .open(open)
+            .resizable(false)
+            .default_width(250.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    egui::TextEdit::singleline(&mut self.name)
+                        .hint_text("Name")
+                        .desired_width(150.)
+                        .ui(ui);
+                    if ui.button("Add current view").clicked() {
+                        let name = if self.name.is_empty() {
+                            format!("View {}", self.bookmarks.len() + 1)
+                        } else {
+                            std::mem::take(&mut self.name)
+                        };
+                        self.bookmarks.push((name, signal_plot.bounds()));
+                    }
+                });
+                ui.separator();
+                let mut delete = None;
+                egui::Grid::new("bookmarks")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (i, (name, bounds)) in self.bookmarks.iter().enumerate() {
+                            ui.label(if i < NUMBER_KEYS.len() {
+                                format!("{}", i + 1)
+                            } else {
+                                "".to_owned()
+                            });
+                            if ui.link(name).clicked() {
+                                signal_plot.set_bounds(*bounds);
+                            }
+                            ui.label(human_readable_time(
+                                bounds.range_x().start().max(0.) / sample_rate,
+                            ));
+                            if ui.small_button("✖").clicked() {
+                                delete = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(i) = delete {
+                    self.bookmarks.remove(i);
+                }
+            });
+    }
+
+    /// 数字键 1-9 跳转到对应的书签
+    pub fn handle_keys(&self, ctx: &egui::Context, signal_plot: &mut SignalPlot) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        for (key, (_, bounds)) in NUMBER_KEYS.iter().zip(self.bookmarks.iter()) {
+            if ctx.input(|i| i.key_pressed(*key) && i.modifiers.is_none()) {
+                signal_plot.set_bounds(*bounds);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bookmarks.clear();
+    }
+}
@@ -0,0 +1,84 @@
+/// 估计噪底时最多使用的样本数
+const NOISE_FLOOR_SAMPLES: usize = 1_000_000;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ThresholdMode {
+    /// 幅度的绝对值
+    Absolute,
+    /// 相对于噪底的 dB 数
+    RelativeDb,
+}
+
+#[derive(Clone, Copy)]
+pub struct BurstParams {
+    pub mode: ThresholdMode,
+    pub threshold: f32,
+    /// 短于这个长度 (样本) 的突发会被丢弃
+    pub min_len: usize,
+    /// 低于门限多少个样本之后才认为突发结束
+    pub hangover: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct Burst {
+    pub start: usize,
+    pub len: usize,
+    /// 峰值功率, 以满幅 1.0 为参考
+    pub peak_db: f32,
+}
+
+/// 用幅度的中位数估计噪底. 突发比较稀疏时, 中位数基本落在噪声上
+pub fn estimate_noise_floor<T>(data: &[T], value: impl Fn(&T) -> f32) -> f32 {
+    if data.is_empty() {
+        return 0.;
+    }
+    let step = (data.len() / NOISE_FLOOR_SAMPLES).max(1);
+    let mut samples: Vec<f32> = data.iter().step_by(step).map(value).collect();
+    let mid = samples.len() / 2;
+    let (_, median, _) = samples.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+    *median
+}
+
+/// `value` 应该返回非负的幅度
+pub fn detect_bursts<T>(data: &[T], value: impl Fn(&T) -> f32, params: BurstParams) -> Vec<Burst> {
+    let threshold = match params.mode {
+        ThresholdMode::Absolute => params.threshold,
+        ThresholdMode::RelativeDb => {
+            estimate_noise_floor(data, &value) * 10f32.powf(params.threshold / 20.)
+        }
+    };
+    let mut bursts = Vec::new();
+    let mut start = None;
+    let mut last_above = 0;
+    let mut peak = 0f32;
+    let close = |start: usize, last_above: usize, peak: f32, bursts: &mut Vec<Burst>| {
+        let len = last_above + 1 - start;
+        if len >= params.min_len {
+            bursts.push(Burst {
+                start,
+                len,
+                peak_db: 20. * peak.log10(),
+            });
+        }
+    };
+    for (i, x) in data.iter().enumerate() {
+        let v = value(x);
+        if v >= threshold {
+            if start.is_none() {
+                start = Some(i);
+                peak = 0.;
+            }
+            last_above = i;
+            peak = peak.max(v);
+        } else if let Some(s) = start {
+            if i - last_above > params.hangover {
+                close(s, last_above, peak, &mut bursts);
+                start = None;
+            }
+        }
+    }
+    if let Some(s) = start {
+        close(s, last_above, peak, &mut bursts);
+    }
+    bursts
+}
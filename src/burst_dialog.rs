@@ -0,0 +1,210 @@
+use crate::burst::{detect_bursts, Burst, BurstParams, ThresholdMode};
+use crate::signal_plot::{Signal, SignalPlot};
+use crate::utils::human_readable_time;
+use eframe::egui::{self, Grid, Widget};
+use egui_file_dialog::FileDialog;
+use std::fs::File;
+use std::io::{self, Write};
+use std::thread;
+
+pub struct BurstDialog {
+    params: BurstParams,
+    bursts: Vec<Burst>,
+    selected: Option<usize>,
+    task: Option<thread::JoinHandle<Vec<Burst>>>,
+    file_dialog: FileDialog,
+}
+
+impl Default for BurstDialog {
+    fn default() -> Self {
+        Self {
+            params: BurstParams {
+                mode: ThresholdMode::RelativeDb,
+                threshold: 10.,
+                min_len: 100,
+                hangover: 100,
+            },
+            bursts: Vec::new(),
+            selected: None,
+            task: None,
+            file_dialog: FileDialog::new()
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::new(0., 0.)),
+        }
+    }
+}
+
+impl BurstDialog {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        signal_plot: &mut SignalPlot,
+    ) -> Result<(), String> {
+        let mut result = Ok(());
+        let sample_rate = signal_plot.sample_rate() as f64;
+        if self.task.as_ref().is_some_and(|task| task.is_finished()) {
+            if let Ok(bursts) = self.task.take().unwrap().join() {
+                self.bursts = bursts;
+                self.selected = None;
+            }
+        }
+        if self.task.is_some() {
+            ctx.request_repaint();
+        }
+        egui::Window::new("Bursts")
+            .open(open)
+            .resizable(true)
+            .default_size([400., 400.])
+            .show(ctx, |ui| {
+                Grid::new("burst-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Threshold");
+                        ui.horizontal(|ui| {
+                            let suffix = match self.params.mode {
+                                ThresholdMode::Absolute => "",
+                                ThresholdMode::RelativeDb => " dB",
+                            };
+                            egui::DragValue::new(&mut self.params.threshold)
+                                .speed(0.1)
+                                .suffix(suffix)
+                                .ui(ui);
+                            ui.selectable_value(
+                                &mut self.params.mode,
+                                ThresholdMode::RelativeDb,
+                                "Above noise floor",
+                            );
+                            ui.selectable_value(
+                                &mut self.params.mode,
+                                ThresholdMode::Absolute,
+                                "Absolute",
+                            );
+                        });
+                        ui.end_row();
+                        ui.label("Minimum length");
+                        egui::DragValue::new(&mut self.params.min_len)
+                            .suffix(" samples")
+                            .ui(ui);
+                        ui.end_row();
+                        ui.label("Hangover");
+                        egui::DragValue::new(&mut self.params.hangover)
+                            .suffix(" samples")
+                            .ui(ui);
+                        ui.end_row();
+                    });
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.task.is_none(), egui::Button::new("Detect"))
+                        .clicked()
+                    {
+                        self.detect(signal_plot);
+                    }
+                    if ui
+                        .add_enabled(!self.bursts.is_empty(), egui::Button::new("Export CSV"))
+                        .clicked()
+                    {
+                        self.file_dialog.save_file();
+                    }
+                    if self.task.is_some() {
+                        ui.spinner();
+                    } else {
+                        ui.label(format!("{} bursts", self.bursts.len()));
+                    }
+                });
+                ui.separator();
+                let row_height = ui.spacing().interact_size.y;
+                egui::ScrollArea::vertical().auto_shrink(false).show_rows(
+                    ui,
+                    row_height,
+                    self.bursts.len(),
+                    |ui, rows| {
+                        Grid::new("burst-list")
+                            .num_columns(4)
+                            .striped(true)
+                            .min_row_height(row_height)
+                            .show(ui, |ui| {
+                                for i in rows {
+                                    let burst = self.bursts[i];
+                                    if ui
+                                        .selectable_label(
+                                            self.selected == Some(i),
+                                            format!("#{}", i + 1),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.selected = Some(i);
+                                        // 两边各留出一部分空白
+                                        let margin = burst.len as f64 * 0.2;
+                                        signal_plot.set_x_range(
+                                            burst.start as f64 - margin,
+                                            (burst.start + burst.len) as f64 + margin,
+                                        );
+                                    }
+                                    ui.label(human_readable_time(burst.start as f64 / sample_rate));
+                                    ui.label(human_readable_time(burst.len as f64 / sample_rate));
+                                    ui.label(format!("{:.1} dBFS", burst.peak_db));
+                                    ui.end_row();
+                                }
+                            });
+                    },
+                );
+                self.file_dialog.update(ctx);
+                if let Some(path) = self.file_dialog.take_selected() {
+                    result = self
+                        .export_csv(path.to_str().unwrap(), sample_rate)
+                        .map_err(|err| format!("Failed to export bursts: {}", err));
+                }
+            });
+        result
+    }
+
+    fn detect(&mut self, signal_plot: &SignalPlot) {
+        let (Some(signal), signal_mag) =
+            (signal_plot.shared_signal(), signal_plot.shared_magnitude())
+        else {
+            return;
+        };
+        let params = self.params;
+        self.task = Some(thread::spawn(move || {
+            match (signal.as_ref(), signal_mag.as_deref()) {
+                (Signal::Complex(_), Some(Signal::Real(mag))) => {
+                    detect_bursts(mag.get(0..mag.len(), 1), |&x| x, params)
+                }
+                (Signal::Complex(sig), _) => {
+                    detect_bursts(sig.get(0..sig.len(), 1), |x| x.norm(), params)
+                }
+                (Signal::Real(sig), _) => {
+                    detect_bursts(sig.get(0..sig.len(), 1), |&x| x.abs(), params)
+                }
+            }
+        }));
+    }
+
+    fn export_csv(&self, path: &str, sample_rate: f64) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(
+            file,
+            "start_sample,start_s,length_samples,duration_s,peak_dbfs"
+        )?;
+        for burst in self.bursts.iter() {
+            writeln!(
+                file,
+                "{},{},{},{},{:.2}",
+                burst.start,
+                burst.start as f64 / sample_rate,
+                burst.len,
+                burst.len as f64 / sample_rate,
+                burst.peak_db
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 换信号时清空, 还在进行的检测属于旧的信号, 直接丢弃结果
+    pub fn clear(&mut self) {
+        self.bursts.clear();
+        self.selected = None;
+        self.task = None;
+    }
+}
@@ -0,0 +1,81 @@
+use crate::signal_plot::SignalPlot;
+use crate::utils::{human_readable_freq, human_readable_time};
+use eframe::egui::{self, Grid};
+
+#[derive(Default)]
+pub struct CursorDialog {}
+
+impl CursorDialog {
+    pub fn show(&self, ctx: &egui::Context, open: &mut bool, signal_plot: &SignalPlot) {
+        let cursors = signal_plot.cursors();
+        let sample_rate = signal_plot.sample_rate() as f64;
+        egui::Window::new("Cursors")
+            .open(open)
+            .resizable(false)
+            .default_width(300.)
+            .show(ctx, |ui| {
+                let dt = (cursors.time[1] - cursors.time[0]).abs() / sample_rate;
+                Grid::new("cursor-readout")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("T1");
+                        ui.label(human_readable_time(cursors.time[0] / sample_rate));
+                        ui.end_row();
+                        ui.label("T2");
+                        ui.label(human_readable_time(cursors.time[1] / sample_rate));
+                        ui.end_row();
+                        ui.label("Δt");
+                        ui.label(human_readable_time(dt));
+                        ui.end_row();
+                        ui.label("1/Δt");
+                        if dt > 0. {
+                            ui.label(human_readable_freq(1. / dt));
+                        } else {
+                            ui.label("-");
+                        }
+                        ui.end_row();
+                        ui.label("V1");
+                        ui.label(format!("{:.6}", cursors.level[0]));
+                        ui.end_row();
+                        ui.label("V2");
+                        ui.label(format!("{:.6}", cursors.level[1]));
+                        ui.end_row();
+                        ui.label("ΔV");
+                        ui.label(format!(
+                            "{:.6}",
+                            (cursors.level[1] - cursors.level[0]).abs()
+                        ));
+                        ui.end_row();
+                    });
+                ui.separator();
+                Grid::new("cursor-values")
+                    .num_columns(3)
+                    .striped(true)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.strong("Trace");
+                        ui.strong("@T1");
+                        ui.strong("@T2");
+                        ui.end_row();
+                        let values1 = signal_plot.trace_values(cursors.time[0]);
+                        let values2 = signal_plot.trace_values(cursors.time[1]);
+                        let names = if values1.len() >= values2.len() {
+                            &values1
+                        } else {
+                            &values2
+                        };
+                        for (i, (name, _)) in names.iter().enumerate() {
+                            ui.label(*name);
+                            for values in [&values1, &values2] {
+                                match values.get(i) {
+                                    Some((_, v)) => ui.label(format!("{:.6}", v)),
+                                    None => ui.label("-"),
+                                };
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
@@ -0,0 +1,113 @@
+use eframe::egui::{Color32, CursorIcon, PointerButton};
+use egui_plot::{HLine, PlotBounds, PlotPoint, PlotUi, VLine};
+
+const TIME_CURSOR_COLOR: Color32 = Color32::from_rgb(255, 220, 80);
+const LEVEL_CURSOR_COLOR: Color32 = Color32::from_rgb(220, 120, 255);
+/// 鼠标距离光标多少像素以内可以拖动
+const GRAB_DISTANCE: f32 = 5.;
+
+#[derive(PartialEq, Clone, Copy)]
+enum CursorId {
+    Time(usize),
+    Level(usize),
+}
+
+/// 示波器风格的光标: 两条竖直的时间光标和两条水平的幅度光标
+pub struct Cursors {
+    pub visible: bool,
+    /// 时间光标的位置, 单位为样本
+    pub time: [f64; 2],
+    pub level: [f64; 2],
+    placed: bool,
+    hovered: Option<CursorId>,
+    dragging: Option<CursorId>,
+}
+
+impl Cursors {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            time: [0., 0.],
+            level: [0., 0.],
+            placed: false,
+            hovered: None,
+            dragging: None,
+        }
+    }
+
+    /// 鼠标位于某个光标上或者正在拖动光标, 此时绘图区不应该响应拖动和框选
+    pub fn grabbed(&self) -> bool {
+        self.visible && (self.hovered.is_some() || self.dragging.is_some())
+    }
+
+    pub fn update(&mut self, plot_ui: &mut PlotUi, bounds: &PlotBounds) {
+        if !self.visible {
+            self.hovered = None;
+            self.dragging = None;
+            return;
+        }
+        if !self.placed {
+            let (x1, x2) = (*bounds.range_x().start(), *bounds.range_x().end());
+            let (y1, y2) = (*bounds.range_y().start(), *bounds.range_y().end());
+            self.time = [x1 + (x2 - x1) / 3., x1 + (x2 - x1) * 2. / 3.];
+            self.level = [y1 + (y2 - y1) / 4., y1 + (y2 - y1) * 3. / 4.];
+            self.placed = true;
+        }
+        let response = plot_ui.response().clone();
+        self.hovered = None;
+        if let Some(pointer) = response.hover_pos() {
+            let mut best = GRAB_DISTANCE;
+            for i in 0..2 {
+                let pos = plot_ui.screen_from_plot(PlotPoint::new(self.time[i], self.level[i]));
+                if (pos.x - pointer.x).abs() < best {
+                    best = (pos.x - pointer.x).abs();
+                    self.hovered = Some(CursorId::Time(i));
+                }
+                if (pos.y - pointer.y).abs() < best {
+                    best = (pos.y - pointer.y).abs();
+                    self.hovered = Some(CursorId::Level(i));
+                }
+            }
+        }
+        if response.drag_started_by(PointerButton::Primary) {
+            self.dragging = self.hovered;
+        }
+        if response.drag_stopped_by(PointerButton::Primary) {
+            self.dragging = None;
+        }
+        if let (Some(id), Some(pointer)) = (self.dragging, plot_ui.pointer_coordinate()) {
+            match id {
+                CursorId::Time(i) => self.time[i] = pointer.x,
+                CursorId::Level(i) => self.level[i] = pointer.y,
+            }
+        }
+        match self.dragging.or(self.hovered) {
+            Some(CursorId::Time(_)) => plot_ui.ctx().set_cursor_icon(CursorIcon::ResizeHorizontal),
+            Some(CursorId::Level(_)) => plot_ui.ctx().set_cursor_icon(CursorIcon::ResizeVertical),
+            None => {}
+        }
+    }
+
+    pub fn draw(&self, plot_ui: &mut PlotUi) {
+        if !self.visible {
+            return;
+        }
+        for i in 0..2 {
+            plot_ui.vline(
+                VLine::new(self.time[i])
+                    .color(TIME_CURSOR_COLOR)
+                    .style(egui_plot::LineStyle::dashed_loose()),
+            );
+            plot_ui.hline(
+                HLine::new(self.level[i])
+                    .color(LEVEL_CURSOR_COLOR)
+                    .style(egui_plot::LineStyle::dashed_loose()),
+            );
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.placed = false;
+        self.dragging = None;
+    }
+}
@@ -0,0 +1,531 @@
+use std::ops::Range;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum LineCoding {
+    None,
+    /// IEEE 802.3: 01 表示 1, 10 表示 0
+    ManchesterIeee,
+    /// G. E. Thomas: 10 表示 1, 01 表示 0
+    ManchesterThomas,
+    /// 电平不变表示 1, 翻转表示 0 (HDLC, USB)
+    Nrzi,
+    /// 差分解码, 电平翻转表示 1 (NRZ-M, 差分 PSK)
+    Differential,
+}
+
+impl LineCoding {
+    pub const ALL: [LineCoding; 5] = [
+        LineCoding::None,
+        LineCoding::ManchesterIeee,
+        LineCoding::ManchesterThomas,
+        LineCoding::Nrzi,
+        LineCoding::Differential,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineCoding::None => "None",
+            LineCoding::ManchesterIeee => "Manchester (IEEE)",
+            LineCoding::ManchesterThomas => "Manchester (Thomas)",
+            LineCoding::Nrzi => "NRZI",
+            LineCoding::Differential => "Differential",
+        }
+    }
+}
+
+/// Rocksoft 模型的 CRC 参数. 宽度只能是 8, 16 或 32 位, CRC 按整字节附在包的末尾
+#[derive(PartialEq, Clone, Copy)]
+pub struct CrcParams {
+    pub width: u32,
+    pub poly: u32,
+    pub init: u32,
+    pub reflect_in: bool,
+    pub reflect_out: bool,
+    pub xor_out: u32,
+}
+
+impl CrcParams {
+    pub const PRESETS: [(&'static str, CrcParams); 4] = [
+        (
+            "CRC-8",
+            CrcParams {
+                width: 8,
+                poly: 0x07,
+                init: 0,
+                reflect_in: false,
+                reflect_out: false,
+                xor_out: 0,
+            },
+        ),
+        (
+            "CRC-16/CCITT-FALSE",
+            CrcParams {
+                width: 16,
+                poly: 0x1021,
+                init: 0xffff,
+                reflect_in: false,
+                reflect_out: false,
+                xor_out: 0,
+            },
+        ),
+        (
+            "CRC-16/ARC",
+            CrcParams {
+                width: 16,
+                poly: 0x8005,
+                init: 0,
+                reflect_in: true,
+                reflect_out: true,
+                xor_out: 0,
+            },
+        ),
+        (
+            "CRC-32",
+            CrcParams {
+                width: 32,
+                poly: 0x04c11db7,
+                init: 0xffffffff,
+                reflect_in: true,
+                reflect_out: true,
+                xor_out: 0xffffffff,
+            },
+        ),
+    ];
+
+    fn mask(&self) -> u32 {
+        debug_assert!(
+            matches!(self.width, 8 | 16 | 32),
+            "unsupported CRC width {}",
+            self.width
+        );
+        u32::MAX >> (32 - self.width)
+    }
+
+    pub fn compute(&self, data: &[u8]) -> u32 {
+        let top = 1u32 << (self.width - 1);
+        let mut crc = self.init & self.mask();
+        for &byte in data {
+            let byte = if self.reflect_in {
+                byte.reverse_bits()
+            } else {
+                byte
+            };
+            for i in (0..8).rev() {
+                let bit = (byte >> i) & 1 == 1;
+                let feedback = (crc & top != 0) ^ bit;
+                crc = (crc << 1) & self.mask();
+                if feedback {
+                    crc ^= self.poly & self.mask();
+                }
+            }
+        }
+        if self.reflect_out {
+            crc = crc.reverse_bits() >> (32 - self.width);
+        }
+        (crc ^ self.xor_out) & self.mask()
+    }
+
+    /// 检查末尾的 CRC 字节. 输出反射的 CRC 按小端发送, 否则按大端发送
+    pub fn check(&self, bytes: &[u8]) -> Option<bool> {
+        let len = (self.width / 8) as usize;
+        if bytes.len() <= len {
+            return None;
+        }
+        let (data, tail) = bytes.split_at(bytes.len() - len);
+        let received = if self.reflect_out {
+            tail.iter().rev().fold(0, |crc, &b| crc << 8 | b as u32)
+        } else {
+            tail.iter().fold(0, |crc, &b| crc << 8 | b as u32)
+        };
+        Some(self.compute(data) == received)
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub struct DecoderParams {
+    pub coding: LineCoding,
+    /// 解码前先把比特取反, 用于 PSK 的相位模糊
+    pub invert: bool,
+    /// 同步字, "0x" 开头的按十六进制解析, 否则按二进制解析. 为空时整个比特流作为一个包
+    pub sync_word: String,
+    /// 同步字允许的错误比特数
+    pub max_errors: usize,
+    /// 同步字之后的包长度 (字节), 0 表示一直到下一个同步字
+    pub packet_len: usize,
+    pub crc_enabled: bool,
+    pub crc: CrcParams,
+}
+
+impl Default for DecoderParams {
+    fn default() -> Self {
+        Self {
+            coding: LineCoding::None,
+            invert: false,
+            sync_word: String::new(),
+            max_errors: 0,
+            packet_len: 0,
+            crc_enabled: false,
+            crc: CrcParams::PRESETS[1].1,
+        }
+    }
+}
+
+pub struct Packet {
+    /// 同步字起始的比特序号
+    pub start: usize,
+    /// 同步字之后的数据所占的比特
+    pub payload: Range<usize>,
+    pub bytes: Vec<u8>,
+    pub crc: Option<bool>,
+}
+
+pub struct Decoded {
+    pub bits: Vec<u8>,
+    /// 每个比特在原始信号中的位置
+    pub positions: Vec<f64>,
+    pub packets: Vec<Packet>,
+}
+
+/// 线路解码, 再按同步字分包并校验 CRC. `positions` 是每个输入比特的位置
+pub fn decode(
+    bits: &[u8],
+    positions: &[f64],
+    params: &DecoderParams,
+) -> Result<Decoded, &'static str> {
+    let sync = parse_pattern(&params.sync_word).ok_or("Invalid sync word!")?;
+    let bits: Vec<u8> = bits.iter().map(|&bit| bit ^ params.invert as u8).collect();
+    let (bits, positions) = decode_line(&bits, positions, params.coding);
+    let packet_bits = params.packet_len * 8;
+    let mut packets = Vec::new();
+    if sync.is_empty() {
+        if !bits.is_empty() {
+            packets.push((0, 0..bits.len()));
+        }
+    } else {
+        let starts = find_pattern(&bits, &sync, params.max_errors);
+        for (i, &start) in starts.iter().enumerate() {
+            let payload_start = start + sync.len();
+            let mut end = starts.get(i + 1).copied().unwrap_or(bits.len());
+            if packet_bits > 0 {
+                end = end.min(payload_start + packet_bits);
+            }
+            packets.push((start, payload_start..end));
+        }
+    }
+    let packets = packets
+        .into_iter()
+        .map(|(start, payload)| {
+            let bytes = pack_bytes(&bits[payload.clone()]);
+            let crc = params
+                .crc_enabled
+                .then(|| params.crc.check(&bytes))
+                .flatten();
+            Packet {
+                start,
+                payload,
+                bytes,
+                crc,
+            }
+        })
+        .collect();
+    Ok(Decoded {
+        bits,
+        positions,
+        packets,
+    })
+}
+
+fn decode_line(bits: &[u8], positions: &[f64], coding: LineCoding) -> (Vec<u8>, Vec<f64>) {
+    let mut output = Vec::with_capacity(bits.len());
+    let mut output_positions = Vec::with_capacity(bits.len());
+    match coding {
+        LineCoding::None => return (bits.to_vec(), positions.to_vec()),
+        LineCoding::ManchesterIeee | LineCoding::ManchesterThomas => {
+            // 遇到无效的半比特对 (00 或 11) 时后移半个比特重新对齐
+            let mut i = 0;
+            while i + 1 < bits.len() {
+                if bits[i] == bits[i + 1] {
+                    i += 1;
+                    continue;
+                }
+                let bit = if coding == LineCoding::ManchesterIeee {
+                    bits[i + 1]
+                } else {
+                    bits[i]
+                };
+                output.push(bit);
+                output_positions.push(positions[i]);
+                i += 2;
+            }
+        }
+        LineCoding::Nrzi | LineCoding::Differential => {
+            for i in 1..bits.len() {
+                let changed = bits[i] ^ bits[i - 1];
+                output.push(if coding == LineCoding::Nrzi {
+                    changed ^ 1
+                } else {
+                    changed
+                });
+                output_positions.push(positions[i]);
+            }
+        }
+    }
+    (output, output_positions)
+}
+
+/// 解析同步字. "0x" 开头的按十六进制, 否则按二进制, 空格和下划线会被忽略
+pub fn parse_pattern(text: &str) -> Option<Vec<u8>> {
+    let text: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '_')
+        .collect();
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        let mut bits = Vec::with_capacity(hex.len() * 4);
+        for c in hex.chars() {
+            let digit = c.to_digit(16)? as u8;
+            bits.extend((0..4).rev().map(|i| (digit >> i) & 1));
+        }
+        return Some(bits);
+    }
+    text.chars()
+        .map(|c| match c {
+            '0' => Some(0),
+            '1' => Some(1),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 查找所有不重叠的匹配位置, 允许最多 `max_errors` 个比特不同
+fn find_pattern(bits: &[u8], pattern: &[u8], max_errors: usize) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i + pattern.len() <= bits.len() {
+        let errors = bits[i..i + pattern.len()]
+            .iter()
+            .zip(pattern)
+            .filter(|(a, b)| a != b)
+            .count();
+        if errors <= max_errors {
+            starts.push(i);
+            i += pattern.len();
+        } else {
+            i += 1;
+        }
+    }
+    starts
+}
+
+/// 按高位在前把比特打包成字节, 最后不满一个字节的部分低位补 0
+pub fn pack_bytes(bits: &[u8]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, &bit)| byte | bit << (7 - i))
+        })
+        .collect()
+}
+
+pub fn format_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 每 8 个比特一组
+pub fn format_binary(bits: &[u8]) -> String {
+    bits.chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .map(|bit| (b'0' + bit) as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 高位在前展开成比特
+    fn to_bits(bytes: &[u8]) -> Vec<u8> {
+        bytes
+            .iter()
+            .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+            .collect()
+    }
+
+    fn positions(len: usize) -> Vec<f64> {
+        (0..len).map(|i| i as f64).collect()
+    }
+
+    fn decode_bits(bits: &[u8], params: &DecoderParams) -> Decoded {
+        decode(bits, &positions(bits.len()), params).unwrap()
+    }
+
+    fn preset(name: &str) -> CrcParams {
+        CrcParams::PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn crc_check_values() {
+        // 各算法对 "123456789" 的标准校验值
+        let data = b"123456789";
+        assert_eq!(preset("CRC-8").compute(data), 0xf4);
+        assert_eq!(preset("CRC-16/CCITT-FALSE").compute(data), 0x29b1);
+        assert_eq!(preset("CRC-16/ARC").compute(data), 0xbb3d);
+        assert_eq!(preset("CRC-32").compute(data), 0xcbf43926);
+    }
+
+    #[test]
+    fn crc_check_trailing_bytes() {
+        let data = b"123456789";
+        for (name, crc) in CrcParams::PRESETS {
+            let value = crc.compute(data);
+            let len = (crc.width / 8) as usize;
+            let mut tail: Vec<u8> = (0..len).rev().map(|i| (value >> (8 * i)) as u8).collect();
+            if crc.reflect_out {
+                tail.reverse();
+            }
+            let mut packet = data.to_vec();
+            packet.extend(&tail);
+            assert_eq!(crc.check(&packet), Some(true), "{}", name);
+            packet[0] ^= 1;
+            assert_eq!(crc.check(&packet), Some(false), "{}", name);
+            assert_eq!(crc.check(&tail), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn manchester_round_trip() {
+        let data = to_bits(&[0xa5, 0x3c]);
+        for coding in [LineCoding::ManchesterIeee, LineCoding::ManchesterThomas] {
+            // IEEE 的 1 编码为 01, Thomas 相反
+            let one = if coding == LineCoding::ManchesterIeee {
+                [0, 1]
+            } else {
+                [1, 0]
+            };
+            let encoded: Vec<u8> = data
+                .iter()
+                .flat_map(|&bit| if bit == 1 { one } else { [one[1], one[0]] })
+                .collect();
+            let params = DecoderParams {
+                coding,
+                ..Default::default()
+            };
+            let decoded = decode_bits(&encoded, &params);
+            assert_eq!(decoded.bits, data);
+            assert_eq!(decoded.positions[1], 2.);
+        }
+    }
+
+    #[test]
+    fn manchester_realigns_on_invalid_pair() {
+        // 开头多出的半个比特使第一对为 00, 后移一位后重新对齐
+        let params = DecoderParams {
+            coding: LineCoding::ManchesterIeee,
+            ..Default::default()
+        };
+        let decoded = decode_bits(&[0, 0, 1, 1, 0], &params);
+        assert_eq!(decoded.bits, [1, 0]);
+        assert_eq!(decoded.positions, [1., 3.]);
+    }
+
+    #[test]
+    fn nrzi_and_differential_round_trip() {
+        let data = to_bits(&[0xa5, 0x3c]);
+        for coding in [LineCoding::Nrzi, LineCoding::Differential] {
+            // 第一个比特作为参考电平
+            let mut encoded = vec![1];
+            for &bit in &data {
+                let toggle = if coding == LineCoding::Nrzi {
+                    bit == 0
+                } else {
+                    bit == 1
+                };
+                let last = *encoded.last().unwrap();
+                encoded.push(last ^ toggle as u8);
+            }
+            let params = DecoderParams {
+                coding,
+                ..Default::default()
+            };
+            let decoded = decode_bits(&encoded, &params);
+            assert_eq!(decoded.bits, data);
+            // 反相不影响差分解码
+            let inverted = DecoderParams {
+                invert: true,
+                ..params
+            };
+            assert_eq!(decode_bits(&encoded, &inverted).bits, data);
+        }
+    }
+
+    #[test]
+    fn parse_sync_word() {
+        assert_eq!(parse_pattern("0x2d"), Some(to_bits(&[0x2d])));
+        assert_eq!(parse_pattern("0010 1101"), Some(to_bits(&[0x2d])));
+        assert_eq!(parse_pattern("1_0"), Some(vec![1, 0]));
+        assert_eq!(parse_pattern(""), Some(Vec::new()));
+        assert_eq!(parse_pattern("0x2g"), None);
+        assert_eq!(parse_pattern("102"), None);
+    }
+
+    #[test]
+    fn sync_word_splits_packets() {
+        let mut bits = vec![1, 0, 1];
+        bits.extend(to_bits(&[0x2d, 0xd4, 0x01, 0x02]));
+        // 第二个同步字有一个比特错误
+        bits.extend(to_bits(&[0x2d, 0xd5, 0x03]));
+        let mut params = DecoderParams {
+            sync_word: "0x2dd4".to_owned(),
+            ..Default::default()
+        };
+        let decoded = decode_bits(&bits, &params);
+        assert_eq!(decoded.packets.len(), 1);
+        assert_eq!(decoded.packets[0].start, 3);
+        assert_eq!(decoded.packets[0].bytes, [0x01, 0x02, 0x2d, 0xd5, 0x03]);
+
+        params.max_errors = 1;
+        let decoded = decode_bits(&bits, &params);
+        assert_eq!(decoded.packets.len(), 2);
+        assert_eq!(decoded.packets[0].bytes, [0x01, 0x02]);
+        assert_eq!(decoded.packets[1].start, 3 + 32);
+        assert_eq!(decoded.packets[1].bytes, [0x03]);
+
+        // 固定包长时在同步字之后只取指定的字节数
+        params.packet_len = 1;
+        let decoded = decode_bits(&bits, &params);
+        assert_eq!(decoded.packets[0].bytes, [0x01]);
+        assert_eq!(decoded.packets[0].payload, 19..27);
+    }
+
+    #[test]
+    fn packet_crc_result() {
+        let crc = preset("CRC-16/CCITT-FALSE");
+        let value = crc.compute(&[0x01, 0x02]);
+        let bits = to_bits(&[0x2d, 0xd4, 0x01, 0x02, (value >> 8) as u8, value as u8]);
+        let mut params = DecoderParams {
+            sync_word: "0x2dd4".to_owned(),
+            crc_enabled: true,
+            crc,
+            ..Default::default()
+        };
+        assert_eq!(decode_bits(&bits, &params).packets[0].crc, Some(true));
+        params.crc_enabled = false;
+        assert_eq!(decode_bits(&bits, &params).packets[0].crc, None);
+        params.crc_enabled = true;
+        params.invert = true;
+        assert!(decode_bits(&bits, &params).packets.is_empty());
+    }
+}
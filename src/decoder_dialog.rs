@@ -0,0 +1,261 @@
+use crate::decoder::{
+    decode, format_binary, format_hex, pack_bytes, CrcParams, Decoded, DecoderParams, LineCoding,
+};
+use crate::digital::Bitstream;
+use crate::signal_plot::SignalPlot;
+use crate::utils::human_readable_time;
+use eframe::egui::{self, Grid, Widget};
+use std::sync::Arc;
+
+pub struct DecoderDialog {
+    params: DecoderParams,
+    /// 按十六进制还是二进制显示
+    hex: bool,
+    /// 当前结果对应的比特流和参数, 变化时重新解码
+    decoded_from: Option<(Arc<Bitstream>, DecoderParams, bool)>,
+    decoded: Result<Decoded, &'static str>,
+    bits_text: String,
+    selected: Option<usize>,
+}
+
+impl Default for DecoderDialog {
+    fn default() -> Self {
+        Self {
+            params: DecoderParams::default(),
+            hex: true,
+            decoded_from: None,
+            decoded: Err("No bitstream, run the digital demodulator first."),
+            bits_text: String::new(),
+            selected: None,
+        }
+    }
+}
+
+impl DecoderDialog {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        bitstream: Option<Arc<Bitstream>>,
+        signal_plot: &mut SignalPlot,
+    ) {
+        let sample_rate = signal_plot.sample_rate() as f64;
+        egui::Window::new("Bit Decoder")
+            .open(open)
+            .resizable(true)
+            .default_size([500., 500.])
+            .show(ctx, |ui| {
+                Grid::new("decoder-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Line Coding");
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("decoder-coding")
+                                .selected_text(self.params.coding.name())
+                                .show_ui(ui, |ui| {
+                                    for coding in LineCoding::ALL {
+                                        ui.selectable_value(
+                                            &mut self.params.coding,
+                                            coding,
+                                            coding.name(),
+                                        );
+                                    }
+                                });
+                            ui.checkbox(&mut self.params.invert, "Invert");
+                        });
+                        ui.end_row();
+                        ui.label("Display");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut self.hex, true, "Hex");
+                            ui.selectable_value(&mut self.hex, false, "Binary");
+                        });
+                        ui.end_row();
+                        ui.label("Sync Word");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.params.sync_word)
+                                .hint_text("0x2dd4 or 1010...")
+                                .desired_width(160.),
+                        );
+                        ui.end_row();
+                        ui.label("Max Errors");
+                        egui::DragValue::new(&mut self.params.max_errors)
+                            .range(0..=16)
+                            .suffix(" bits")
+                            .ui(ui);
+                        ui.end_row();
+                        ui.label("Packet Length");
+                        egui::DragValue::new(&mut self.params.packet_len)
+                            .suffix(" bytes")
+                            .ui(ui)
+                            .on_hover_text("0 splits at the next sync word");
+                        ui.end_row();
+                        ui.label("CRC");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.params.crc_enabled, "");
+                            let preset = CrcParams::PRESETS
+                                .iter()
+                                .find(|(_, crc)| *crc == self.params.crc)
+                                .map(|(name, _)| *name)
+                                .unwrap_or("Custom");
+                            ui.add_enabled_ui(self.params.crc_enabled, |ui| {
+                                egui::ComboBox::from_id_salt("decoder-crc")
+                                    .selected_text(preset)
+                                    .show_ui(ui, |ui| {
+                                        for (name, crc) in CrcParams::PRESETS {
+                                            ui.selectable_value(&mut self.params.crc, crc, name);
+                                        }
+                                    });
+                            });
+                        });
+                        ui.end_row();
+                        if self.params.crc_enabled {
+                            crc_rows(ui, &mut self.params.crc);
+                        }
+                    });
+                self.redecode(bitstream);
+                ui.separator();
+                let decoded = match &self.decoded {
+                    Ok(decoded) => decoded,
+                    Err(msg) => {
+                        ui.colored_label(egui::Color32::LIGHT_RED, *msg);
+                        return;
+                    }
+                };
+                ui.label(format!(
+                    "{} bits, {} packets",
+                    decoded.bits.len(),
+                    decoded.packets.len()
+                ));
+                egui::CollapsingHeader::new("Bitstream").show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_salt("decoder-bits")
+                        .max_height(120.)
+                        .show(ui, |ui| {
+                            ui.add(egui::Label::new(
+                                egui::RichText::new(&self.bits_text).monospace(),
+                            ));
+                        });
+                });
+                ui.separator();
+                let row_height = ui.spacing().interact_size.y;
+                egui::ScrollArea::both()
+                    .id_salt("decoder-packets")
+                    .auto_shrink(false)
+                    .show_rows(ui, row_height, decoded.packets.len(), |ui, rows| {
+                        Grid::new("decoder-packet-list")
+                            .num_columns(5)
+                            .striped(true)
+                            .min_row_height(row_height)
+                            .show(ui, |ui| {
+                                for i in rows {
+                                    let packet = &decoded.packets[i];
+                                    if ui
+                                        .selectable_label(
+                                            self.selected == Some(i),
+                                            format!("#{}", i + 1),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.selected = Some(i);
+                                        let start = decoded.positions[packet.start];
+                                        let end = decoded.positions
+                                            [packet.payload.end.max(packet.start + 1) - 1];
+                                        // 两边各留出一部分空白
+                                        let margin = (end - start).max(1.) * 0.2;
+                                        signal_plot.set_x_range(start - margin, end + margin);
+                                    }
+                                    ui.label(human_readable_time(
+                                        decoded.positions[packet.start] / sample_rate,
+                                    ));
+                                    ui.label(format!("{} bytes", packet.bytes.len()));
+                                    match packet.crc {
+                                        Some(true) => {
+                                            ui.colored_label(egui::Color32::LIGHT_GREEN, "CRC OK")
+                                        }
+                                        Some(false) => {
+                                            ui.colored_label(egui::Color32::LIGHT_RED, "CRC FAIL")
+                                        }
+                                        None => ui.label("-"),
+                                    };
+                                    let data = if self.hex {
+                                        format_hex(&packet.bytes)
+                                    } else {
+                                        format_binary(&decoded.bits[packet.payload.clone()])
+                                    };
+                                    ui.label(egui::RichText::new(data).monospace());
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+    }
+
+    /// 解码结果的位置只对当前信号有效, 换信号时清空
+    pub fn clear(&mut self) {
+        *self = Self {
+            params: self.params.clone(),
+            hex: self.hex,
+            ..Default::default()
+        };
+    }
+
+    fn redecode(&mut self, bitstream: Option<Arc<Bitstream>>) {
+        let Some(bitstream) = bitstream else {
+            self.decoded_from = None;
+            self.decoded = Err("No bitstream, run the digital demodulator first.");
+            return;
+        };
+        if let Some((from, params, hex)) = &self.decoded_from {
+            if Arc::ptr_eq(from, &bitstream) && *params == self.params && *hex == self.hex {
+                return;
+            }
+        }
+        // QPSK 每个符号有两个比特, 它们的位置相同
+        let bits_per_symbol = bitstream.mode.bits_per_symbol();
+        let positions: Vec<f64> = (0..bitstream.bits.len())
+            .map(|i| bitstream.positions[i / bits_per_symbol])
+            .collect();
+        self.decoded = decode(&bitstream.bits, &positions, &self.params);
+        self.bits_text = match &self.decoded {
+            Ok(decoded) if self.hex => format_hex(&pack_bytes(&decoded.bits)),
+            Ok(decoded) => format_binary(&decoded.bits),
+            Err(_) => String::new(),
+        };
+        self.selected = None;
+        self.decoded_from = Some((bitstream, self.params.clone(), self.hex));
+    }
+}
+
+/// 自定义 CRC 参数的行
+fn crc_rows(ui: &mut egui::Ui, crc: &mut CrcParams) {
+    ui.label("Width");
+    ui.horizontal(|ui| {
+        for width in [8, 16, 32] {
+            ui.selectable_value(&mut crc.width, width, format!("{} bits", width));
+        }
+    });
+    ui.end_row();
+    let mask = u32::MAX >> (32 - crc.width);
+    let digits = (crc.width / 4) as usize;
+    for (name, value) in [
+        ("Polynomial", &mut crc.poly),
+        ("Initial Value", &mut crc.init),
+        ("Final XOR", &mut crc.xor_out),
+    ] {
+        *value &= mask;
+        ui.label(name);
+        egui::DragValue::new(value)
+            .hexadecimal(digits, false, false)
+            .range(0..=mask)
+            .prefix("0x")
+            .ui(ui);
+        ui.end_row();
+    }
+    ui.label("Reflect");
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut crc.reflect_in, "Input");
+        ui.checkbox(&mut crc.reflect_out, "Output");
+    });
+    ui.end_row();
+}
@@ -0,0 +1,203 @@
+use crate::dsp::{downconvert, fir_filter, frequency_shift, lowpass_taps};
+use crate::fft::Window;
+use rayon::prelude::*;
+use rustfft::num_complex::Complex;
+use std::f64::consts::PI;
+
+/// SSB 边带滤波器的抽头数上限
+const MAX_SSB_TAPS: usize = 4095;
+/// SSB 先抽取到音频带宽的这么多倍, 再在低采样率上做陡峭的边带滤波
+const SSB_OVERSAMPLE: f64 = 4.;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum AnalogMode {
+    Am,
+    Fm,
+    Usb,
+    Lsb,
+}
+
+impl AnalogMode {
+    pub const ALL: [AnalogMode; 4] = [
+        AnalogMode::Am,
+        AnalogMode::Fm,
+        AnalogMode::Usb,
+        AnalogMode::Lsb,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnalogMode::Am => "AM",
+            AnalogMode::Fm => "FM",
+            AnalogMode::Usb => "USB",
+            AnalogMode::Lsb => "LSB",
+        }
+    }
+}
+
+/// FM 去加重的时间常数
+#[derive(PartialEq, Clone, Copy)]
+pub enum Deemphasis {
+    None,
+    Us50,
+    Us75,
+}
+
+impl Deemphasis {
+    pub const ALL: [Deemphasis; 3] = [Deemphasis::None, Deemphasis::Us50, Deemphasis::Us75];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Deemphasis::None => "None",
+            Deemphasis::Us50 => "50 µs",
+            Deemphasis::Us75 => "75 µs",
+        }
+    }
+
+    fn tau(&self) -> Option<f64> {
+        match self {
+            Deemphasis::None => None,
+            Deemphasis::Us50 => Some(50e-6),
+            Deemphasis::Us75 => Some(75e-6),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub struct AnalogParams {
+    pub mode: AnalogMode,
+    pub deemphasis: Deemphasis,
+    /// SSB 的音频带宽, 单位 Hz
+    pub bandwidth: f64,
+}
+
+impl Default for AnalogParams {
+    fn default() -> Self {
+        Self {
+            mode: AnalogMode::Fm,
+            deemphasis: Deemphasis::None,
+            bandwidth: 3000.,
+        }
+    }
+}
+
+/// 解调复基带信号, 载波位于零频. AM 输出去掉直流的包络, FM 输出瞬时频率 (Hz), SSB 输出音频.
+/// 返回解调结果和抽取倍数, 第 k 个输出样本对应第 `k * decimation` 个输入样本
+pub fn demodulate(
+    data: &[Complex<f32>],
+    params: &AnalogParams,
+    sample_rate: f64,
+) -> (Vec<f32>, usize) {
+    match params.mode {
+        AnalogMode::Am => {
+            let envelope: Vec<f32> = data.par_iter().map(|x| x.norm()).collect();
+            let mean =
+                envelope.iter().map(|&x| x as f64).sum::<f64>() / envelope.len().max(1) as f64;
+            (envelope.par_iter().map(|&x| x - mean as f32).collect(), 1)
+        }
+        AnalogMode::Fm => {
+            let scale = sample_rate / (2. * PI);
+            // 正交鉴频: 相邻样本的相位差
+            let mut output: Vec<f32> = (0..data.len())
+                .into_par_iter()
+                .map(|i| {
+                    if i == 0 {
+                        return 0.;
+                    }
+                    let x = data[i] * data[i - 1].conj();
+                    (x.im.atan2(x.re) as f64 * scale) as f32
+                })
+                .collect();
+            if let Some(tau) = params.deemphasis.tau() {
+                deemphasize(&mut output, tau, sample_rate);
+            }
+            (output, 1)
+        }
+        AnalogMode::Usb | AnalogMode::Lsb => {
+            // 把边带的中心移到零频并抽取, 在低采样率上用低通取出边带, 再移回去取实部.
+            // 在原始采样率上滤波需要的抽头数太多, 过渡带会比边带还宽
+            let center = if params.mode == AnalogMode::Usb {
+                params.bandwidth / 2.
+            } else {
+                -params.bandwidth / 2.
+            };
+            let (baseband, decimation) = downconvert(
+                data,
+                |x| Complex::new(x.re as f64, x.im as f64),
+                center,
+                params.bandwidth * SSB_OVERSAMPLE,
+                sample_rate,
+            );
+            let rate = sample_rate / decimation as f64;
+            let cutoff = (params.bandwidth / 2. / rate).min(0.5);
+            let num_taps = ((64. / cutoff) as usize).clamp(31, MAX_SSB_TAPS) | 1;
+            let taps = lowpass_taps(cutoff, num_taps, Window::BlackmanHarris);
+            let filtered = fir_filter(&baseband, &taps);
+            let to_complex = |x: &Complex<f32>| Complex::new(x.re as f64, x.im as f64);
+            let output = frequency_shift(&filtered, to_complex, center, rate)
+                .par_iter()
+                .map(|x| x.re)
+                .collect();
+            (output, decimation)
+        }
+    }
+}
+
+/// 单极点低通, 时间常数为 `tau`
+fn deemphasize(data: &mut [f32], tau: f64, sample_rate: f64) {
+    let alpha = (1. - (-1. / (sample_rate * tau)).exp()) as f32;
+    let mut y = 0f32;
+    for x in data.iter_mut() {
+        y += alpha * (*x - y);
+        *x = y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 用相关计算 `freq` 处单音的幅度
+    fn tone_amplitude(data: &[f32], freq: f64, sample_rate: f64) -> f64 {
+        let sum = data
+            .iter()
+            .enumerate()
+            .fold(Complex::new(0., 0.), |sum, (n, &x)| {
+                sum + Complex::from_polar(x as f64, -2. * PI * freq * n as f64 / sample_rate)
+            });
+        2. * sum.norm() / data.len() as f64
+    }
+
+    #[test]
+    fn ssb_rejects_opposite_sideband() {
+        // 2.4 MS/s 下上边带 1 kHz, 下边带 1.5 kHz 各一个单音
+        let sample_rate = 2_400_000.;
+        let data: Vec<Complex<f32>> = (0..480_000)
+            .map(|n| {
+                let t = n as f64 / sample_rate;
+                let x = Complex::from_polar(1., 2. * PI * 1000. * t)
+                    + Complex::from_polar(1., -2. * PI * 1500. * t);
+                Complex::new(x.re as f32, x.im as f32)
+            })
+            .collect();
+        for (mode, wanted, unwanted) in [
+            (AnalogMode::Usb, 1000., 1500.),
+            (AnalogMode::Lsb, 1500., 1000.),
+        ] {
+            let params = AnalogParams {
+                mode,
+                ..Default::default()
+            };
+            let (output, decimation) = demodulate(&data, &params, sample_rate);
+            assert!(decimation > 1);
+            assert_eq!(output.len(), data.len().div_ceil(decimation));
+            // 去掉两端滤波器的暂态
+            let rate = sample_rate / decimation as f64;
+            let middle = &output[output.len() / 4..output.len() * 3 / 4];
+            let wanted = tone_amplitude(middle, wanted, rate);
+            let unwanted = tone_amplitude(middle, unwanted, rate);
+            assert!((wanted - 1.).abs() < 0.05, "{}", wanted);
+            assert!(20. * (unwanted / wanted).log10() < -50., "{}", unwanted);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::demod::{demodulate, AnalogMode, AnalogParams, Deemphasis};
+use crate::series::MultiResolutionSeries;
+use crate::signal_plot::{DerivedTrace, Signal, SignalPlot};
+use crate::utils::{format_freq, parse_freq};
+use crate::wav::write_wav;
+use eframe::egui::{self, Grid, Widget};
+use egui_file_dialog::FileDialog;
+use std::sync::Arc;
+use std::thread;
+
+/// 可以解调的信号: 原始信号或者派生曲线
+pub struct Source {
+    pub name: String,
+    pub signal: Arc<Signal>,
+    pub offset: f64,
+    pub step: f64,
+}
+
+pub struct DemodDialog {
+    params: AnalogParams,
+    /// 在可选信号源中的下标
+    source: usize,
+    task: Option<thread::JoinHandle<(DerivedTrace, u32)>>,
+    /// 最近一次解调的结果和它的采样率, 用于导出 WAV
+    output: Option<(Arc<Signal>, u32)>,
+    file_dialog: FileDialog,
+}
+
+impl Default for DemodDialog {
+    fn default() -> Self {
+        Self {
+            params: AnalogParams::default(),
+            source: 0,
+            task: None,
+            output: None,
+            file_dialog: FileDialog::new()
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::new(0., 0.)),
+        }
+    }
+}
+
+impl DemodDialog {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        signal_plot: &mut SignalPlot,
+    ) -> Result<(), String> {
+        let mut result = Ok(());
+        if self.task.as_ref().is_some_and(|task| task.is_finished()) {
+            if let Ok((trace, sample_rate)) = self.task.take().unwrap().join() {
+                self.output = Some((trace.signal.clone(), sample_rate));
+                signal_plot.add_derived(trace);
+            }
+        }
+        if self.task.is_some() {
+            ctx.request_repaint();
+        }
+        let sources = signal_sources(signal_plot, true);
+        if self.source >= sources.len() {
+            self.source = 0;
+        }
+        let sample_rate = signal_plot.sample_rate() as f64;
+        egui::Window::new("Demodulate")
+            .open(open)
+            .resizable(false)
+            .default_width(300.)
+            .show(ctx, |ui| {
+                Grid::new("demod-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Source");
+                        egui::ComboBox::from_id_salt("demod-source")
+                            .selected_text(
+                                sources
+                                    .get(self.source)
+                                    .map(|source| source.name.as_str())
+                                    .unwrap_or("-"),
+                            )
+                            .show_ui(ui, |ui| {
+                                for (i, source) in sources.iter().enumerate() {
+                                    ui.selectable_value(&mut self.source, i, &source.name);
+                                }
+                            });
+                        ui.end_row();
+                        analog_params_rows(ui, &mut self.params);
+                    });
+                ui.add_space(10.);
+                ui.horizontal(|ui| {
+                    let enabled = self.task.is_none() && !sources.is_empty();
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Demodulate"))
+                        .clicked()
+                    {
+                        if let Some(source) = sources.get(self.source) {
+                            self.demodulate(source, sample_rate);
+                        }
+                    }
+                    if ui
+                        .add_enabled(self.output.is_some(), egui::Button::new("Export WAV"))
+                        .clicked()
+                    {
+                        self.file_dialog.save_file();
+                    }
+                    if self.task.is_some() {
+                        ui.spinner();
+                    }
+                });
+                if sources.is_empty() {
+                    ui.label("No complex signal to demodulate.");
+                }
+                self.file_dialog.update(ctx);
+                if let Some(path) = self.file_dialog.take_selected() {
+                    result = self
+                        .export_wav(path.to_str().unwrap())
+                        .map_err(|err| format!("Failed to export WAV: {}", err));
+                }
+            });
+        result
+    }
+
+    fn demodulate(&mut self, source: &Source, sample_rate: f64) {
+        let signal = source.signal.clone();
+        let params = self.params;
+        let (offset, step) = (source.offset, source.step);
+        let sample_rate = sample_rate / step;
+        let name = format!("{} {}", params.mode.name(), source.name);
+        self.task = Some(thread::spawn(move || {
+            let Signal::Complex(sig) = signal.as_ref() else {
+                unreachable!("only complex signals are listed as sources");
+            };
+            let (data, decimation) = demodulate(sig.get(0..sig.len(), 1), &params, sample_rate);
+            let trace = DerivedTrace {
+                name,
+                signal: Arc::new(Signal::Real(MultiResolutionSeries::build(&data, 2048))),
+                offset,
+                step: step * decimation as f64,
+            };
+            (trace, (sample_rate / decimation as f64).round() as u32)
+        }));
+    }
+
+    fn export_wav(&self, path: &str) -> std::io::Result<()> {
+        let Some((signal, sample_rate)) = &self.output else {
+            return Ok(());
+        };
+        if let Signal::Real(sig) = signal.as_ref() {
+            write_wav(path, sig.get(0..sig.len(), 1), *sample_rate)?;
+        }
+        Ok(())
+    }
+
+    /// 解调结果只对当前信号有效, 换信号时丢弃
+    pub fn clear(&mut self) {
+        self.task = None;
+        self.output = None;
+    }
+}
+
+/// 在两列的 `Grid` 中添加解调方式和相应参数的行
+pub fn analog_params_rows(ui: &mut egui::Ui, params: &mut AnalogParams) {
+    ui.label("Mode");
+    ui.horizontal(|ui| {
+        for mode in AnalogMode::ALL {
+            ui.selectable_value(&mut params.mode, mode, mode.name());
+        }
+    });
+    ui.end_row();
+    match params.mode {
+        AnalogMode::Fm => {
+            ui.label("De-emphasis");
+            egui::ComboBox::from_id_salt("demod-deemphasis")
+                .selected_text(params.deemphasis.name())
+                .show_ui(ui, |ui| {
+                    for deemphasis in Deemphasis::ALL {
+                        ui.selectable_value(&mut params.deemphasis, deemphasis, deemphasis.name());
+                    }
+                });
+            ui.end_row();
+        }
+        AnalogMode::Usb | AnalogMode::Lsb => {
+            ui.label("Bandwidth");
+            egui::DragValue::new(&mut params.bandwidth)
+                .custom_formatter(|f, _range| format_freq(f))
+                .custom_parser(parse_freq)
+                .range(1.0..=f64::MAX)
+                .speed(10.0)
+                .ui(ui);
+            ui.end_row();
+        }
+        AnalogMode::Am => {}
+    }
+}
+
+/// 列出原始信号和所有派生曲线, `complex_only` 时只列出复信号
+pub fn signal_sources(signal_plot: &SignalPlot, complex_only: bool) -> Vec<Source> {
+    let mut sources = Vec::new();
+    if let Some(signal) = signal_plot.shared_signal() {
+        if !complex_only || matches!(signal.as_ref(), Signal::Complex(_)) {
+            sources.push(Source {
+                name: "Signal".to_owned(),
+                signal,
+                offset: 0.,
+                step: 1.,
+            });
+        }
+    }
+    for trace in signal_plot.derived() {
+        if !complex_only || matches!(trace.signal.as_ref(), Signal::Complex(_)) {
+            sources.push(Source {
+                name: trace.name.clone(),
+                signal: trace.signal.clone(),
+                offset: trace.offset,
+                step: trace.step,
+            });
+        }
+    }
+    sources
+}
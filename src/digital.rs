@@ -0,0 +1,481 @@
+use rustfft::{num_complex::Complex, FftPlanner};
+use std::f64::consts::PI;
+
+/// 粗频偏估计使用的最大 FFT 长度
+const MAX_COARSE_FFT: usize = 1 << 16;
+/// 定时环路允许的最大符号率偏差
+const MAX_RATE_OFFSET: f64 = 0.1;
+/// 环路阻尼系数
+const DAMPING: f64 = 0.707;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum DigitalMode {
+    Ook,
+    Fsk,
+    Bpsk,
+    Qpsk,
+}
+
+impl DigitalMode {
+    pub const ALL: [DigitalMode; 4] = [
+        DigitalMode::Ook,
+        DigitalMode::Fsk,
+        DigitalMode::Bpsk,
+        DigitalMode::Qpsk,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DigitalMode::Ook => "OOK/ASK",
+            DigitalMode::Fsk => "2-FSK",
+            DigitalMode::Bpsk => "BPSK",
+            DigitalMode::Qpsk => "QPSK",
+        }
+    }
+
+    pub fn bits_per_symbol(&self) -> usize {
+        match self {
+            DigitalMode::Qpsk => 2,
+            _ => 1,
+        }
+    }
+
+    /// OOK 只用幅度, 实信号也可以解调
+    pub fn needs_complex(&self) -> bool {
+        !matches!(self, DigitalMode::Ook)
+    }
+
+    fn is_psk(&self) -> bool {
+        matches!(self, DigitalMode::Bpsk | DigitalMode::Qpsk)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum TimingRecovery {
+    Gardner,
+    MuellerMuller,
+}
+
+impl TimingRecovery {
+    pub const ALL: [TimingRecovery; 2] = [TimingRecovery::Gardner, TimingRecovery::MuellerMuller];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimingRecovery::Gardner => "Gardner",
+            TimingRecovery::MuellerMuller => "Mueller-Müller",
+        }
+    }
+
+    /// 鉴相器增益, 单位幅度的信号偏离一个符号周期时的误差
+    fn gain(&self) -> f64 {
+        match self {
+            TimingRecovery::Gardner => 2.,
+            TimingRecovery::MuellerMuller => 1.,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub struct DigitalParams {
+    pub mode: DigitalMode,
+    pub timing: TimingRecovery,
+    /// 符号率, 单位 Baud
+    pub symbol_rate: f64,
+    /// 定时和载波环路的归一化带宽 (相对符号率)
+    pub loop_bandwidth: f64,
+}
+
+impl Default for DigitalParams {
+    fn default() -> Self {
+        Self {
+            mode: DigitalMode::Ook,
+            timing: TimingRecovery::Gardner,
+            symbol_rate: 10_000.,
+            loop_bandwidth: 0.01,
+        }
+    }
+}
+
+/// 解调得到的比特流
+pub struct Bitstream {
+    /// 每个元素是一个比特, 取值 0 或 1
+    pub bits: Vec<u8>,
+    /// 每个符号的判决时刻, 单位为输入样本
+    pub positions: Vec<f64>,
+    pub mode: DigitalMode,
+    /// OOK 的判决门限, FSK 的中心频率或 PSK 的载波频偏 (Hz)
+    pub level: f64,
+}
+
+impl Bitstream {
+    /// 第 `index` 个符号的取值, 用于在图上区分不同的符号
+    pub fn symbol(&self, index: usize) -> u8 {
+        let len = self.mode.bits_per_symbol();
+        let bits = &self.bits[index * len..(index + 1) * len];
+        bits.iter().fold(0, |value, &bit| value << 1 | bit)
+    }
+}
+
+/// 解调一段复信号 (实信号虚部为 0). 先匹配滤波, 再做载波和符号定时同步, 最后逐个符号判决
+pub fn demodulate_digital(
+    data: &[Complex<f32>],
+    params: &DigitalParams,
+    sample_rate: f64,
+) -> Result<Bitstream, &'static str> {
+    let sps = sample_rate / params.symbol_rate;
+    if sps < 2. {
+        return Err("Symbol rate must be at most half the sample rate!");
+    }
+    if (data.len() as f64) < 4. * sps {
+        return Err("Selection is too short for the symbol rate!");
+    }
+    // 矩形脉冲的匹配滤波器是一个符号长的滑动平均
+    let matched = (sps.round() as usize).max(1);
+    let mut delay = (matched - 1) as f64 / 2.;
+    let samples: Vec<Complex<f64>> = data
+        .iter()
+        .map(|x| Complex::new(x.re as f64, x.im as f64))
+        .collect();
+    let (baseband, level) = match params.mode {
+        DigitalMode::Ook => {
+            let envelope = moving_average(
+                &samples.iter().map(|x| x.norm()).collect::<Vec<_>>(),
+                matched,
+            );
+            let threshold = two_level_threshold(&envelope);
+            let baseband = envelope
+                .iter()
+                .map(|&x| Complex::new(x - threshold, 0.))
+                .collect();
+            (baseband, threshold)
+        }
+        DigitalMode::Fsk => {
+            // 鉴频器的第 n 个输出位于样本 n 和 n+1 之间
+            delay -= 0.5;
+            let freq: Vec<f64> = samples
+                .windows(2)
+                .map(|w| (w[1] * w[0].conj()).arg())
+                .collect();
+            let freq = moving_average(&freq, matched);
+            let center = two_level_threshold(&freq);
+            let baseband = freq.iter().map(|&f| Complex::new(f - center, 0.)).collect();
+            (baseband, center * sample_rate / (2. * PI))
+        }
+        DigitalMode::Bpsk | DigitalMode::Qpsk => {
+            let filtered = moving_average(&samples, matched);
+            // M 次方去掉调制后剩下 M 倍的载波频偏
+            let power = if params.mode == DigitalMode::Bpsk {
+                2
+            } else {
+                4
+            };
+            let offset = coarse_frequency(&filtered, power);
+            let baseband = filtered
+                .iter()
+                .enumerate()
+                .map(|(n, x)| x * Complex::from_polar(1., -2. * PI * (offset * n as f64).fract()))
+                .collect();
+            (baseband, offset * sample_rate)
+        }
+    };
+    let baseband = normalize(baseband);
+    let (positions, symbols) = synchronize(&baseband, sps, params);
+    let mut bits = Vec::with_capacity(symbols.len() * params.mode.bits_per_symbol());
+    for z in symbols {
+        match params.mode {
+            DigitalMode::Qpsk => {
+                bits.push((z.re > 0.) as u8);
+                bits.push((z.im > 0.) as u8);
+            }
+            _ => bits.push((z.re > 0.) as u8),
+        }
+    }
+    Ok(Bitstream {
+        bits,
+        positions: positions.iter().map(|t| t - delay).collect(),
+        mode: params.mode,
+        level,
+    })
+}
+
+/// 符号定时同步, PSK 同时用 Costas 环跟踪载波相位. 返回判决时刻和去旋转后的符号
+fn synchronize(
+    data: &[Complex<f64>],
+    sps: f64,
+    params: &DigitalParams,
+) -> (Vec<f64>, Vec<Complex<f64>>) {
+    let (timing_p, timing_i) = loop_gains(params.loop_bandwidth, params.timing.gain());
+    let (carrier_p, carrier_i) = loop_gains(params.loop_bandwidth, 1.);
+    let mut positions = Vec::new();
+    let mut symbols = Vec::new();
+    let mut t = sps;
+    let mut rate = 0.;
+    let mut phase = 0f64;
+    let mut freq = 0.;
+    let mut prev: Option<(Complex<f64>, Complex<f64>)> = None;
+    while t + 1. < data.len() as f64 {
+        let rotation = Complex::from_polar(1., -phase);
+        let y = interpolate(data, t) * rotation;
+        let d = decide(y, params.mode);
+        if params.mode.is_psk() {
+            // 判决导向的 Costas 环, 误差是符号和判决之间的相位差
+            let error = (y * d.conj()).im.clamp(-1., 1.);
+            freq += carrier_i * error;
+            phase = (phase + freq + carrier_p * error).rem_euclid(2. * PI);
+        }
+        let mut error = 0.;
+        if let Some((y_prev, d_prev)) = prev {
+            // 误差为正表示采样时刻偏晚
+            error = match params.timing {
+                TimingRecovery::Gardner => {
+                    let mid = interpolate(data, t - sps / 2.) * rotation;
+                    ((y - y_prev) * mid.conj()).re
+                }
+                TimingRecovery::MuellerMuller => (d.conj() * y_prev - d_prev.conj() * y).re,
+            }
+            .clamp(-1., 1.);
+        }
+        positions.push(t);
+        symbols.push(y);
+        prev = Some((y, d));
+        rate = (rate + timing_i * error).clamp(-MAX_RATE_OFFSET, MAX_RATE_OFFSET);
+        t += sps * (1. - rate - timing_p * error);
+    }
+    (positions, symbols)
+}
+
+/// 二阶环路滤波器的比例和积分增益
+fn loop_gains(bandwidth: f64, detector_gain: f64) -> (f64, f64) {
+    let theta = bandwidth / (DAMPING + 1. / (4. * DAMPING));
+    let d = 1. + 2. * DAMPING * theta + theta * theta;
+    (
+        4. * DAMPING * theta / d / detector_gain,
+        4. * theta * theta / d / detector_gain,
+    )
+}
+
+/// 硬判决得到最近的星座点
+fn decide(y: Complex<f64>, mode: DigitalMode) -> Complex<f64> {
+    match mode {
+        DigitalMode::Qpsk => Complex::new(y.re.signum(), y.im.signum()) / 2f64.sqrt(),
+        _ => Complex::new(y.re.signum(), 0.),
+    }
+}
+
+fn interpolate(data: &[Complex<f64>], t: f64) -> Complex<f64> {
+    let i = t.floor() as usize;
+    let frac = t - i as f64;
+    data[i] * (1. - frac) + data[(i + 1).min(data.len() - 1)] * frac
+}
+
+/// 长度为 `len` 的滑动平均, 输出和输入等长
+fn moving_average<T>(data: &[T], len: usize) -> Vec<T>
+where
+    T: Copy + Default + std::ops::AddAssign + std::ops::SubAssign + std::ops::Div<f64, Output = T>,
+{
+    let mut sum = T::default();
+    data.iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            sum += x;
+            if i >= len {
+                sum -= data[i - len];
+            }
+            sum / len.min(i + 1) as f64
+        })
+        .collect()
+}
+
+/// 把数据分成高低两类 (k-means), 返回两类均值的中点
+fn two_level_threshold(data: &[f64]) -> f64 {
+    let mut low = data.iter().copied().fold(f64::INFINITY, f64::min);
+    let mut high = data.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    for _ in 0..20 {
+        let threshold = (low + high) / 2.;
+        let (mut sum_low, mut count_low, mut sum_high, mut count_high) = (0., 0, 0., 0);
+        for &x in data {
+            if x < threshold {
+                sum_low += x;
+                count_low += 1;
+            } else {
+                sum_high += x;
+                count_high += 1;
+            }
+        }
+        if count_low == 0 || count_high == 0 {
+            break;
+        }
+        low = sum_low / count_low as f64;
+        high = sum_high / count_high as f64;
+    }
+    (low + high) / 2.
+}
+
+/// 用 M 次方谱的峰值估计载波频偏, 单位为周期每样本
+fn coarse_frequency(data: &[Complex<f64>], power: i32) -> f64 {
+    let mut len = MAX_COARSE_FFT;
+    while len > data.len() {
+        len >>= 1;
+    }
+    let mut buffer: Vec<Complex<f64>> = data[..len].iter().map(|x| x.powi(power)).collect();
+    FftPlanner::new().plan_fft_forward(len).process(&mut buffer);
+    let peak = buffer
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.norm_sqr().total_cmp(&b.1.norm_sqr()))
+        .map(|(k, _)| k)
+        .unwrap_or(0);
+    let freq = peak as f64 / len as f64;
+    let freq = if freq >= 0.5 { freq - 1. } else { freq };
+    freq / power as f64
+}
+
+/// 归一化到单位均方根幅度, 使环路增益和信号幅度无关
+fn normalize(data: Vec<Complex<f64>>) -> Vec<Complex<f64>> {
+    let rms = (data.iter().map(|x| x.norm_sqr()).sum::<f64>() / data.len().max(1) as f64).sqrt();
+    if rms == 0. {
+        return data;
+    }
+    data.into_iter().map(|x| x / rms).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f64 = 100_000.;
+    const SYMBOL_RATE: f64 = 10_000.;
+    /// 发送端的每符号样本数, 符号率偏高 0.1%
+    const SPS: f64 = SAMPLE_RATE / (SYMBOL_RATE * 1.001);
+    /// 第一个符号的起始时刻, 单位为样本
+    const DELAY: f64 = 3.3;
+
+    /// 线性反馈移位寄存器生成的伪随机比特
+    fn prbs(len: usize) -> Vec<u8> {
+        let mut state = 0x5au8;
+        (0..len)
+            .map(|_| {
+                let bit = ((state >> 6) ^ (state >> 5)) & 1;
+                state = (state << 1 | bit) & 0x7f;
+                bit
+            })
+            .collect()
+    }
+
+    /// 矩形脉冲调制, 符号率和起始时刻都有偏差, 载波有频偏和相偏
+    fn modulate(symbols: &[Complex<f64>], freq_offset: f64) -> Vec<Complex<f32>> {
+        let len = (symbols.len() as f64 * SPS + DELAY) as usize;
+        (0..len)
+            .map(|n| {
+                let k = ((n as f64 - DELAY) / SPS).floor().max(0.) as usize;
+                let phase = 2. * PI * freq_offset * n as f64 / SAMPLE_RATE + 0.7;
+                let x = symbols[k.min(symbols.len() - 1)] * Complex::from_polar(1., phase);
+                Complex::new(x.re as f32, x.im as f32)
+            })
+            .collect()
+    }
+
+    /// 跳过环路收敛的部分后, 在所有对齐位置和相位模糊中找到最少的错误比特数
+    fn min_errors(decoded: &[u8], candidates: &[Vec<u8>], bits_per_symbol: usize) -> usize {
+        let skip = 200 * bits_per_symbol;
+        let compare = 400 * bits_per_symbol;
+        let decoded = &decoded[skip..skip + compare];
+        candidates
+            .iter()
+            .flat_map(|expected| {
+                (0..=40 * bits_per_symbol)
+                    .step_by(bits_per_symbol)
+                    .map(move |shift| {
+                        let start = skip + shift - 20 * bits_per_symbol;
+                        decoded
+                            .iter()
+                            .zip(&expected[start..start + compare])
+                            .filter(|(a, b)| a != b)
+                            .count()
+                    })
+            })
+            .min()
+            .unwrap()
+    }
+
+    /// 收敛后的判决时刻应该在符号中间附近
+    fn assert_centered(positions: &[f64]) {
+        for &position in &positions[200..] {
+            let phase = ((position - DELAY) / SPS).rem_euclid(1.);
+            assert!((phase - 0.5).abs() < 0.1, "{}", phase);
+        }
+    }
+
+    #[test]
+    fn bpsk_round_trip() {
+        let bits = prbs(800);
+        let symbols: Vec<_> = bits
+            .iter()
+            .map(|&bit| Complex::new(2. * bit as f64 - 1., 0.))
+            .collect();
+        let data = modulate(&symbols, 230.);
+        // BPSK 有 180 度的相位模糊
+        let inverted: Vec<u8> = bits.iter().map(|bit| bit ^ 1).collect();
+        for timing in TimingRecovery::ALL {
+            let params = DigitalParams {
+                mode: DigitalMode::Bpsk,
+                timing,
+                ..Default::default()
+            };
+            let bitstream = demodulate_digital(&data, &params, SAMPLE_RATE).unwrap();
+            assert!((bitstream.level - 230.).abs() < 20., "{}", bitstream.level);
+            let errors = min_errors(&bitstream.bits, &[bits.clone(), inverted.clone()], 1);
+            assert_eq!(errors, 0, "{}", timing.name());
+            assert_centered(&bitstream.positions);
+        }
+    }
+
+    #[test]
+    fn qpsk_round_trip() {
+        let bits = prbs(1600);
+        let symbols: Vec<_> = bits
+            .chunks(2)
+            .map(|pair| Complex::new(2. * pair[0] as f64 - 1., 2. * pair[1] as f64 - 1.))
+            .collect();
+        let data = modulate(&symbols, -410.);
+        // QPSK 有 90 度的相位模糊, 把发送的符号依次旋转 90 度得到所有可能的比特
+        let candidates: Vec<Vec<u8>> = (0..4)
+            .map(|k| {
+                let rotation = Complex::new(0., 1.).powi(k);
+                symbols
+                    .iter()
+                    .flat_map(|&z| {
+                        let z = z * rotation;
+                        [(z.re > 0.) as u8, (z.im > 0.) as u8]
+                    })
+                    .collect()
+            })
+            .collect();
+        for timing in TimingRecovery::ALL {
+            let params = DigitalParams {
+                mode: DigitalMode::Qpsk,
+                timing,
+                ..Default::default()
+            };
+            let bitstream = demodulate_digital(&data, &params, SAMPLE_RATE).unwrap();
+            let errors = min_errors(&bitstream.bits, &candidates, 2);
+            assert_eq!(errors, 0, "{}", timing.name());
+            assert_centered(&bitstream.positions);
+        }
+    }
+
+    #[test]
+    fn ook_round_trip() {
+        let bits = prbs(800);
+        let symbols: Vec<_> = bits
+            .iter()
+            .map(|&bit| Complex::new(bit as f64, 0.))
+            .collect();
+        let data = modulate(&symbols, 1500.);
+        let params = DigitalParams::default();
+        let bitstream = demodulate_digital(&data, &params, SAMPLE_RATE).unwrap();
+        assert!((bitstream.level - 0.5).abs() < 0.1, "{}", bitstream.level);
+        assert_eq!(min_errors(&bitstream.bits, &[bits], 1), 0);
+        assert_centered(&bitstream.positions);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::decoder::format_binary;
+use crate::demod_dialog::{signal_sources, Source};
+use crate::digital::{demodulate_digital, Bitstream, DigitalMode, DigitalParams, TimingRecovery};
+use crate::signal_plot::{Signal, SignalPlot};
+use crate::utils::human_readable_freq;
+use eframe::egui::{self, Grid, Widget};
+use rustfft::num_complex::Complex;
+use std::sync::Arc;
+use std::thread;
+
+#[derive(Default)]
+pub struct DigitalDialog {
+    params: DigitalParams,
+    /// 在可选信号源中的下标
+    source: usize,
+    task: Option<thread::JoinHandle<Result<Bitstream, &'static str>>>,
+    /// 最近一次解调的比特流, 判决时刻已经换算成原始信号的样本位置
+    bitstream: Option<Arc<Bitstream>>,
+    /// 比特流按字节分组后的文本
+    bits_text: String,
+}
+
+impl DigitalDialog {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        signal_plot: &mut SignalPlot,
+    ) -> Result<(), String> {
+        let mut result = Ok(());
+        if self.task.as_ref().is_some_and(|task| task.is_finished()) {
+            match self.task.take().unwrap().join() {
+                Ok(Ok(bitstream)) => {
+                    self.set_bitstream(bitstream, signal_plot);
+                }
+                Ok(Err(msg)) => result = Err(msg.to_owned()),
+                Err(_) => {}
+            }
+        }
+        if self.task.is_some() {
+            ctx.request_repaint();
+        }
+        let sources = signal_sources(signal_plot, false);
+        if self.source >= sources.len() {
+            self.source = 0;
+        }
+        let sample_rate = signal_plot.sample_rate() as f64;
+        let range = signal_plot.selected_range();
+        egui::Window::new("Digital Demodulate")
+            .open(open)
+            .resizable(false)
+            .default_width(320.)
+            .show(ctx, |ui| {
+                Grid::new("digital-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Source");
+                        egui::ComboBox::from_id_salt("digital-source")
+                            .selected_text(
+                                sources
+                                    .get(self.source)
+                                    .map(|source| source.name.as_str())
+                                    .unwrap_or("-"),
+                            )
+                            .show_ui(ui, |ui| {
+                                for (i, source) in sources.iter().enumerate() {
+                                    ui.selectable_value(&mut self.source, i, &source.name);
+                                }
+                            });
+                        ui.end_row();
+                        ui.label("Modulation");
+                        ui.horizontal(|ui| {
+                            for mode in DigitalMode::ALL {
+                                ui.selectable_value(&mut self.params.mode, mode, mode.name());
+                            }
+                        });
+                        ui.end_row();
+                        ui.label("Timing Recovery");
+                        egui::ComboBox::from_id_salt("digital-timing")
+                            .selected_text(self.params.timing.name())
+                            .show_ui(ui, |ui| {
+                                for timing in TimingRecovery::ALL {
+                                    ui.selectable_value(
+                                        &mut self.params.timing,
+                                        timing,
+                                        timing.name(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+                        ui.label("Symbol Rate");
+                        egui::DragValue::new(&mut self.params.symbol_rate)
+                            .suffix(" Bd")
+                            .range(1.0..=f64::MAX)
+                            .speed(10.0)
+                            .ui(ui);
+                        ui.end_row();
+                        ui.label("Loop Bandwidth");
+                        egui::DragValue::new(&mut self.params.loop_bandwidth)
+                            .range(0.0001..=0.2)
+                            .speed(0.0005)
+                            .max_decimals(4)
+                            .ui(ui)
+                            .on_hover_text("Normalized to the symbol rate");
+                        ui.end_row();
+                    });
+                let source = sources.get(self.source);
+                let usable = source.is_some_and(|source| {
+                    !self.params.mode.needs_complex()
+                        || matches!(source.signal.as_ref(), Signal::Complex(_))
+                });
+                ui.add_space(10.);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            usable && self.task.is_none(),
+                            egui::Button::new("Demodulate"),
+                        )
+                        .on_hover_text("Demodulate the selected region")
+                        .clicked()
+                    {
+                        if let Some(source) = source {
+                            self.demodulate(source, range.clone(), sample_rate);
+                        }
+                    }
+                    if ui
+                        .add_enabled(self.bitstream.is_some(), egui::Button::new("Clear"))
+                        .clicked()
+                    {
+                        self.clear();
+                        signal_plot.set_symbols(Vec::new());
+                    }
+                    if self.task.is_some() {
+                        ui.spinner();
+                    }
+                });
+                if source.is_some() && !usable {
+                    ui.label(format!(
+                        "{} needs a complex source.",
+                        self.params.mode.name()
+                    ));
+                }
+                if let Some(bitstream) = &self.bitstream {
+                    ui.separator();
+                    Grid::new("digital-result")
+                        .num_columns(2)
+                        .spacing([40.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Symbols");
+                            ui.label(bitstream.positions.len().to_string());
+                            ui.end_row();
+                            ui.label("Bits");
+                            ui.label(bitstream.bits.len().to_string());
+                            ui.end_row();
+                            match bitstream.mode {
+                                DigitalMode::Ook => {
+                                    ui.label("Threshold");
+                                    ui.label(format!("{:.4}", bitstream.level));
+                                }
+                                DigitalMode::Fsk => {
+                                    ui.label("Center Frequency (baseband)");
+                                    ui.label(human_readable_freq(bitstream.level));
+                                }
+                                DigitalMode::Bpsk | DigitalMode::Qpsk => {
+                                    ui.label("Carrier Offset (baseband)");
+                                    ui.label(human_readable_freq(bitstream.level));
+                                }
+                            }
+                            ui.end_row();
+                        });
+                    egui::ScrollArea::vertical()
+                        .max_height(150.)
+                        .show(ui, |ui| {
+                            ui.add(egui::Label::new(
+                                egui::RichText::new(&self.bits_text).monospace(),
+                            ));
+                        });
+                }
+            });
+        result
+    }
+
+    /// 在后台解调信号源中和选中区间重叠的部分
+    fn demodulate(&mut self, source: &Source, range: std::ops::Range<usize>, sample_rate: f64) {
+        let signal = source.signal.clone();
+        let params = self.params;
+        let (offset, step) = (source.offset, source.step);
+        let sample_rate = sample_rate / step;
+        self.task = Some(thread::spawn(move || {
+            // 选中区间换算成信号源自身的样本序号
+            let start = ((range.start as f64 - offset) / step).floor().max(0.) as usize;
+            let end =
+                (((range.end as f64 - offset) / step).ceil().max(0.) as usize).min(signal.len());
+            if start >= end {
+                return Err("Selection does not overlap the source!");
+            }
+            let data = match signal.as_ref() {
+                Signal::Real(sig) => sig
+                    .get(start..end, 1)
+                    .iter()
+                    .map(|&x| Complex::new(x, 0.))
+                    .collect(),
+                Signal::Complex(sig) => sig.get(start..end, 1).to_vec(),
+            };
+            let mut bitstream = demodulate_digital(&data, &params, sample_rate)?;
+            for position in bitstream.positions.iter_mut() {
+                *position = offset + (start as f64 + *position) * step;
+            }
+            Ok(bitstream)
+        }));
+    }
+
+    /// 比特流的位置只对当前信号有效, 换信号时清空并丢弃进行中的解调
+    pub fn clear(&mut self) {
+        self.task = None;
+        self.bitstream = None;
+        self.bits_text.clear();
+    }
+
+    pub fn bitstream(&self) -> Option<Arc<Bitstream>> {
+        self.bitstream.clone()
+    }
+
+    fn set_bitstream(&mut self, bitstream: Bitstream, signal_plot: &mut SignalPlot) {
+        let max_symbol = ((1 << bitstream.mode.bits_per_symbol()) - 1) as f32;
+        signal_plot.set_symbols(
+            bitstream
+                .positions
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| (x, bitstream.symbol(i) as f32 / max_symbol))
+                .collect(),
+        );
+        self.bits_text = format_binary(&bitstream.bits);
+        self.bitstream = Some(Arc::new(bitstream));
+    }
+}
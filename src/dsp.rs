@@ -0,0 +1,237 @@
+use crate::fft::Window;
+use rayon::prelude::*;
+use rustfft::num_complex::Complex;
+use std::f64::consts::PI;
+use std::ops::{Add, Mul};
+
+/// 每个并行任务处理的输出样本数
+const CHUNK_SIZE: usize = 1 << 16;
+/// 低通滤波器最多的抽头数
+const MAX_TAPS: usize = 4095;
+/// 重采样时的滤波器工作在插值后的采样率上, 允许更长
+const MAX_RESAMPLE_TAPS: usize = 1 << 17;
+
+/// 加窗 sinc 低通滤波器, `cutoff` 为归一化截止频率 (相对采样率), 直流增益为 1
+pub fn lowpass_taps(cutoff: f64, num_taps: usize, window: Window) -> Vec<f64> {
+    let coefficients = window.coefficients(num_taps, 8.6);
+    let center = (num_taps - 1) as f64 / 2.;
+    let mut taps: Vec<f64> = coefficients
+        .iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let t = i as f64 - center;
+            let sinc = if t == 0. {
+                2. * cutoff
+            } else {
+                (2. * PI * cutoff * t).sin() / (PI * t)
+            };
+            sinc * w
+        })
+        .collect();
+    let sum = taps.iter().sum::<f64>();
+    taps.iter_mut().for_each(|tap| *tap /= sum);
+    taps
+}
+
+/// 多级抽取时前面每一级的抽取倍数
+const STAGE_DECIMATION: usize = 8;
+
+/// 把 `center` 处宽 `bandwidth` 的频带搬到基带, 低通滤波后抽取, 类似数字下变频器.
+/// 抽取倍数很大时单级滤波器的抽头不够, 先分几级每级抽取 8 倍, 最后一级再滤出需要的频带.
+/// 返回复数结果和总的抽取倍数, 第 k 个输出样本对应第 `k * decimation` 个输入样本
+pub fn downconvert<T: Sync>(
+    data: &[T],
+    to_complex: impl Fn(&T) -> Complex<f64> + Sync,
+    center: f64,
+    bandwidth: f64,
+    sample_rate: f64,
+) -> (Vec<Complex<f32>>, usize) {
+    // 输出采样率留出 25% 的过渡带
+    let mut remaining = ((sample_rate / (bandwidth * 1.25)).floor() as usize).max(1);
+    let mut stages = Vec::new();
+    while remaining >= STAGE_DECIMATION * STAGE_DECIMATION {
+        stages.push(STAGE_DECIMATION);
+        remaining /= STAGE_DECIMATION;
+    }
+    let step = center / sample_rate;
+    let mut rate = sample_rate;
+    stages.push(remaining);
+    let mut output: Option<Vec<Complex<f64>>> = None;
+    for (i, &decimation) in stages.iter().enumerate() {
+        // 中间级只需要防止混叠到最终的频带里, 截止频率取输出采样率的一半
+        let cutoff = if i + 1 == stages.len() {
+            (bandwidth / 2. / rate).min(0.5)
+        } else {
+            0.5 / decimation as f64
+        };
+        output = Some(match output {
+            None => decimate_stage(data, &to_complex, step, cutoff, decimation),
+            Some(data) => decimate_stage(&data, |&x| x, 0., cutoff, decimation),
+        });
+        rate /= decimation as f64;
+    }
+    let output = output
+        .unwrap_or_default()
+        .iter()
+        .map(|x| Complex::new(x.re as f32, x.im as f32))
+        .collect();
+    (output, stages.iter().product())
+}
+
+/// 下变频的一级: 乘以 e^(-j2π·step·n) 混频, 低通滤波后抽取
+fn decimate_stage<T: Sync>(
+    data: &[T],
+    to_complex: impl Fn(&T) -> Complex<f64> + Sync,
+    step: f64,
+    cutoff: f64,
+    decimation: usize,
+) -> Vec<Complex<f64>> {
+    let num_taps = (decimation * 16 + 1).clamp(31, MAX_TAPS) | 1;
+    let taps = lowpass_taps(cutoff, num_taps, Window::BlackmanHarris);
+    let half = (num_taps / 2) as isize;
+    let output_len = data.len().div_ceil(decimation);
+    let mut output = vec![Complex::new(0., 0.); output_len];
+    output
+        .par_chunks_mut(CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(chunk, output)| {
+            for (k, y) in output.iter_mut().enumerate() {
+                let n = ((chunk * CHUNK_SIZE + k) * decimation) as isize;
+                let mut sum = Complex::new(0., 0.);
+                for (j, tap) in taps.iter().enumerate() {
+                    let i = n + j as isize - half;
+                    if i < 0 || i >= data.len() as isize {
+                        continue;
+                    }
+                    // 先取小数部分, 避免样本序号很大时损失相位精度
+                    let phase = -2. * PI * (step * i as f64).fract();
+                    sum += to_complex(&data[i as usize]) * Complex::from_polar(*tap, phase);
+                }
+                *y = sum;
+            }
+        });
+    output
+}
+
+/// 用 FIR 滤波器 `taps` 对整个序列滤波, 按块并行计算. 输出和输入对齐, 已经补偿了群延时
+pub fn fir_filter<T>(data: &[T], taps: &[f64]) -> Vec<T>
+where
+    T: Copy + Default + Send + Sync + Add<Output = T> + Mul<f32, Output = T>,
+{
+    let taps: Vec<f32> = taps.iter().map(|&tap| tap as f32).collect();
+    let half = taps.len() / 2;
+    let mut output = vec![T::default(); data.len()];
+    output
+        .par_chunks_mut(CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(chunk, output)| {
+            for (k, y) in output.iter_mut().enumerate() {
+                let n = chunk * CHUNK_SIZE + k;
+                // 只累加落在序列范围内的抽头
+                let first = half.saturating_sub(n);
+                let last = (data.len() + half - n).min(taps.len());
+                let mut sum = T::default();
+                for j in first..last {
+                    sum = sum + data[n + j - half] * taps[j];
+                }
+                *y = sum;
+            }
+        });
+    output
+}
+
+/// 复数频移, 乘以 e^(j2π·shift·n/fs). 实信号会变成复信号
+pub fn frequency_shift<T: Sync>(
+    data: &[T],
+    to_complex: impl Fn(&T) -> Complex<f64> + Sync,
+    shift: f64,
+    sample_rate: f64,
+) -> Vec<Complex<f32>> {
+    let step = shift / sample_rate;
+    let mut output = vec![Complex::new(0f32, 0f32); data.len()];
+    output
+        .par_chunks_mut(CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(chunk, output)| {
+            for (k, y) in output.iter_mut().enumerate() {
+                let n = chunk * CHUNK_SIZE + k;
+                let phase = 2. * PI * (step * n as f64).fract();
+                let x = to_complex(&data[n]) * Complex::from_polar(1., phase);
+                *y = Complex::new(x.re as f32, x.im as f32);
+            }
+        });
+    output
+}
+
+/// 重采样滤波器每一倍插值或抽取需要的抽头数
+const TAPS_PER_FACTOR: usize = 16;
+/// 插值和抽取倍数的上限, 保证重采样滤波器不超过最大抽头数
+pub const MAX_FACTOR: usize = (MAX_RESAMPLE_TAPS - 1) / TAPS_PER_FACTOR;
+
+/// 输出采样率和输入采样率之比化成最简分数 up / down
+pub fn rational_factors(input: u32, output: u32) -> Option<(usize, usize)> {
+    if input == 0 || output == 0 {
+        return None;
+    }
+    let (mut a, mut b) = (input, output);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    let (up, down) = ((output / a) as usize, (input / a) as usize);
+    (up <= MAX_FACTOR && down <= MAX_FACTOR).then_some((up, down))
+}
+
+/// 不能精确化简时用分母不超过上限的分数近似, 用于对采样率要求不严格的场合
+pub fn approximate_factors(input: u32, output: u32) -> Option<(usize, usize)> {
+    if input == 0 || output == 0 {
+        return None;
+    }
+    rational_factors(input, output).or_else(|| {
+        let ratio = output as f64 / input as f64;
+        (1..=MAX_FACTOR)
+            .map(|down| ((ratio * down as f64).round().max(1.) as usize, down))
+            .filter(|&(up, _)| up <= MAX_FACTOR)
+            .min_by(|a, b| {
+                let error = |(up, down): (usize, usize)| (up as f64 / down as f64 - ratio).abs();
+                error(*a).total_cmp(&error(*b))
+            })
+    })
+}
+
+/// 有理数倍重采样: 先插零 `up` 倍, 低通滤波后抽取 `down` 倍, 按多相结构只计算用到的乘法
+pub fn resample<T>(data: &[T], up: usize, down: usize) -> Vec<T>
+where
+    T: Copy + Default + Send + Sync + Add<Output = T> + Mul<f32, Output = T>,
+{
+    let factor = up.max(down);
+    let num_taps = (factor * TAPS_PER_FACTOR + 1).min(MAX_RESAMPLE_TAPS) | 1;
+    // 插零后信号幅度变为 1/up, 用滤波器增益补回来
+    let taps: Vec<f32> = lowpass_taps(0.5 / factor as f64, num_taps, Window::BlackmanHarris)
+        .iter()
+        .map(|&tap| (tap * up as f64) as f32)
+        .collect();
+    let half = num_taps / 2;
+    let output_len = (data.len() * up).div_ceil(down);
+    let mut output = vec![T::default(); output_len];
+    output
+        .par_chunks_mut(CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(chunk, output)| {
+            for (k, y) in output.iter_mut().enumerate() {
+                // 插零后序列中的位置, 加上群延时使输出和输入对齐
+                let n = (chunk * CHUNK_SIZE + k) * down + half;
+                let mut sum = T::default();
+                // 只有 n - j 是 up 的倍数的抽头会乘到非零样本
+                let mut j = n % up;
+                while j < taps.len() && j <= n {
+                    let i = (n - j) / up;
+                    if i < data.len() {
+                        sum = sum + data[i] * taps[j];
+                    }
+                    j += up;
+                }
+                *y = sum;
+            }
+        });
+    output
+}
@@ -1,64 +1,261 @@
 use rayon::prelude::*;
 use rustfft::{num_complex::Complex, FftPlanner};
 use std::f64::consts::PI;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-fn hanning_window(size: usize) -> Vec<f64> {
-    (0..size)
-        .map(|i| 0.5 * (1.0 - (2.0 * PI * i as f64 / (size as f64 - 1.0)).cos()))
-        .collect()
+#[derive(PartialEq, Clone, Copy)]
+pub enum Window {
+    Hann,
+    Hamming,
+    BlackmanHarris,
+    FlatTop,
+    Kaiser,
 }
 
-pub fn compute_psd(
-    input: &[Complex<f64>],
-    nfft: usize,
-    noverlap: usize,
-    sample_rate: f64,
-) -> (Vec<f64>, Vec<f64>) {
-    if input.len() < nfft {
-        return compute_psd(
-            &input
-                .iter()
-                .chain(std::iter::repeat(&Complex::new(0.0, 0.0)))
-                .take(nfft)
-                .copied()
-                .collect::<Vec<Complex<f64>>>(),
-            nfft,
-            noverlap,
-            sample_rate,
-        );
+impl Window {
+    pub const ALL: [Window; 5] = [
+        Window::Hann,
+        Window::Hamming,
+        Window::BlackmanHarris,
+        Window::FlatTop,
+        Window::Kaiser,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Window::Hann => "Hann",
+            Window::Hamming => "Hamming",
+            Window::BlackmanHarris => "Blackman-Harris",
+            Window::FlatTop => "Flat-top",
+            Window::Kaiser => "Kaiser",
+        }
+    }
+
+    /// `beta` 只对 Kaiser 窗有效
+    pub fn coefficients(&self, size: usize, beta: f64) -> Vec<f64> {
+        let n = size as f64 - 1.0;
+        // 余弦和窗: a0 - a1 cos(x) + a2 cos(2x) - a3 cos(3x) + a4 cos(4x)
+        let cosine_sum = |a: &[f64]| -> Vec<f64> {
+            (0..size)
+                .map(|i| {
+                    let x = 2.0 * PI * i as f64 / n;
+                    a.iter()
+                        .enumerate()
+                        .map(|(k, &ak)| {
+                            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                            sign * ak * (k as f64 * x).cos()
+                        })
+                        .sum()
+                })
+                .collect()
+        };
+        match self {
+            Window::Hann => cosine_sum(&[0.5, 0.5]),
+            Window::Hamming => cosine_sum(&[0.54, 0.46]),
+            Window::BlackmanHarris => cosine_sum(&[0.35875, 0.48829, 0.14128, 0.01168]),
+            Window::FlatTop => cosine_sum(&[
+                0.21557895,
+                0.41663158,
+                0.277263158,
+                0.083578947,
+                0.006947368,
+            ]),
+            Window::Kaiser => (0..size)
+                .map(|i| {
+                    let r = 2.0 * i as f64 / n - 1.0;
+                    bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / bessel_i0(beta)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 第一类零阶修正贝塞尔函数, 级数展开
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > sum * 1e-12 {
+        term *= (x / (2.0 * k)) * (x / (2.0 * k));
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Averaging {
+    Mean,
+    MaxHold,
+    MinHold,
+}
+
+impl Averaging {
+    pub const ALL: [Averaging; 3] = [Averaging::Mean, Averaging::MaxHold, Averaging::MinHold];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Averaging::Mean => "Mean",
+            Averaging::MaxHold => "Max hold",
+            Averaging::MinHold => "Min hold",
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Scaling {
+    /// 功率谱, 以满幅正弦为参考, 单音的峰值等于它的功率
+    PowerSpectrum,
+    /// 功率谱密度, 除以等效噪声带宽
+    Density,
+}
+
+impl Scaling {
+    pub const ALL: [Scaling; 2] = [Scaling::PowerSpectrum, Scaling::Density];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scaling::PowerSpectrum => "Power spectrum",
+            Scaling::Density => "Power spectral density",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Scaling::PowerSpectrum => "dBFS",
+            Scaling::Density => "dB/Hz",
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub struct PsdParams {
+    pub nfft: usize,
+    pub window: Window,
+    pub kaiser_beta: f64,
+    /// 相邻两段重叠的比例, 0 到 1 之间
+    pub overlap: f64,
+    pub averaging: Averaging,
+    pub scaling: Scaling,
+}
+
+impl Default for PsdParams {
+    fn default() -> Self {
+        Self {
+            nfft: 1024,
+            window: Window::Hann,
+            kaiser_beta: 8.6,
+            overlap: 0.5,
+            averaging: Averaging::Mean,
+            scaling: Scaling::PowerSpectrum,
+        }
+    }
+}
+
+impl PsdParams {
+    pub fn noverlap(&self) -> usize {
+        ((self.nfft as f64 * self.overlap) as usize).min(self.nfft - 1)
+    }
+
+    /// 窗函数的等效噪声带宽, 单位为频点数
+    pub fn enbw_bins(&self) -> f64 {
+        let window = self.window.coefficients(self.nfft, self.kaiser_beta);
+        let sum = window.iter().sum::<f64>();
+        let sum_sq = window.iter().map(|w| w * w).sum::<f64>();
+        self.nfft as f64 * sum_sq / (sum * sum)
     }
+}
+
+/// 每批并行处理的段数, 每批结束后更新进度并检查是否取消
+const SEGMENTS_PER_BATCH: usize = 256;
+
+/// 流式 Welch 方法: 直接从 `data` 中逐段取样本, 不需要先把整个输入转换成复数.
+/// `one_sided` 用于实信号, 只返回非负频率并把负频率的功率叠加上去.
+/// `progress` 记录已经处理的段数, `cancel` 被置位时提前返回 `None`
+#[allow(clippy::too_many_arguments)]
+pub fn compute_psd<T: Sync>(
+    data: &[T],
+    to_complex: impl Fn(&T) -> Complex<f64> + Sync,
+    params: &PsdParams,
+    sample_rate: f64,
+    one_sided: bool,
+    progress: &AtomicUsize,
+    cancel: &AtomicBool,
+) -> Option<(Vec<f64>, Vec<f64>)> {
+    let nfft = params.nfft;
     let mut planner = FftPlanner::new();
     let fft = planner.plan_fft_forward(nfft);
-    let window = hanning_window(nfft);
-    // 使用并行迭代器处理每个段
-    let step = nfft - noverlap;
-    let psd_sum: Vec<f64> = (0..input.len() - nfft + 1)
-        .into_par_iter()
-        .step_by(step)
-        .map(|start| {
-            let end = start + nfft;
-            let segment = &input[start..end];
-            // 应用窗函数
-            let mut windowed_segment: Vec<Complex<f64>> =
-                segment.iter().zip(&window).map(|(&s, &w)| s * w).collect();
-            // 执行 FFT
-            fft.process(&mut windowed_segment);
-            // 计算并返回每个段的功率谱
-            windowed_segment
-                .iter()
-                .map(|&x| x.norm_sqr() / (window.iter().sum::<f64>() * window.iter().sum::<f64>()))
-                .collect::<Vec<f64>>()
-        })
-        .reduce_with(|mut a, b| {
-            for (ai, bi) in a.iter_mut().zip(b.iter()) {
-                *ai += *bi;
-            }
-            a
-        })
-        .unwrap_or_else(|| vec![0.0; nfft]);
+    let window = params.window.coefficients(nfft, params.kaiser_beta);
+    let norm = match params.scaling {
+        Scaling::PowerSpectrum => window.iter().sum::<f64>().powi(2),
+        Scaling::Density => sample_rate * window.iter().map(|w| w * w).sum::<f64>(),
+    };
+    let step = nfft - params.noverlap();
+    let count = segment_count(data.len(), params);
+    let mut psd_sum: Option<Vec<f64>> = None;
+    for batch in (0..count).step_by(SEGMENTS_PER_BATCH) {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        // 使用并行迭代器处理每个段
+        let batch_sum = (batch..(batch + SEGMENTS_PER_BATCH).min(count))
+            .into_par_iter()
+            .map(|segment| {
+                let start = segment * step;
+                // 应用窗函数, 不足 nfft 的部分补零
+                let mut windowed_segment: Vec<Complex<f64>> = window
+                    .iter()
+                    .enumerate()
+                    .map(|(k, &w)| match data.get(start + k) {
+                        Some(s) => to_complex(s) * w,
+                        None => Complex::new(0.0, 0.0),
+                    })
+                    .collect();
+                // 执行 FFT
+                fft.process(&mut windowed_segment);
+                // 计算并返回每个段的功率谱
+                windowed_segment
+                    .iter()
+                    .map(|&x| x.norm_sqr() / norm)
+                    .collect::<Vec<f64>>()
+            })
+            .reduce_with(|a, b| accumulate(a, &b, params.averaging));
+        psd_sum = match (psd_sum, batch_sum) {
+            (Some(a), Some(b)) => Some(accumulate(a, &b, params.averaging)),
+            (a, b) => a.or(b),
+        };
+        progress.fetch_add(
+            (batch + SEGMENTS_PER_BATCH).min(count) - batch,
+            Ordering::Relaxed,
+        );
+    }
+    let psd_sum = psd_sum.unwrap_or_else(|| vec![0.0; nfft]);
     // 计算平均功率谱
-    let count = ((input.len() - nfft + 1) as f64 / step as f64).floor();
+    let count = match params.averaging {
+        Averaging::Mean => count as f64,
+        _ => 1.0,
+    };
     let psd: Vec<f64> = psd_sum.into_iter().map(|x| x / count).collect();
+    if one_sided {
+        // 实信号的频谱是对称的, 除了直流和奈奎斯特频率以外都要乘 2
+        let half_nfft = nfft / 2;
+        let freqs = (0..=half_nfft)
+            .map(|i| i as f64 * sample_rate / nfft as f64)
+            .collect();
+        let log_psd = psd[..=half_nfft]
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let x = if i == 0 || (i == half_nfft && nfft.is_multiple_of(2)) {
+                    x
+                } else {
+                    2.0 * x
+                };
+                10.0 * x.log10()
+            })
+            .collect();
+        return Some((freqs, log_psd));
+    }
     let freqs: Vec<f64> = (0..nfft)
         .map(|i| {
             let freq = i as f64 * sample_rate / nfft as f64;
@@ -84,5 +281,110 @@ pub fn compute_psd(
         .chain(first_half_psd.iter())
         .copied()
         .collect();
-    (shifted_freqs, shifted_psd)
+    Some((shifted_freqs, shifted_psd))
+}
+
+/// 输入长度为 `len` 时的段数, 输入比 nfft 短时补零成一段
+pub fn segment_count(len: usize, params: &PsdParams) -> usize {
+    if len <= params.nfft {
+        1
+    } else {
+        (len - params.nfft) / (params.nfft - params.noverlap()) + 1
+    }
+}
+
+fn accumulate(mut a: Vec<f64>, b: &[f64], averaging: Averaging) -> Vec<f64> {
+    for (ai, bi) in a.iter_mut().zip(b.iter()) {
+        *ai = match averaging {
+            Averaging::Mean => *ai + *bi,
+            Averaging::MaxHold => ai.max(*bi),
+            Averaging::MinHold => ai.min(*bi),
+        };
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn psd_of<T: Sync>(
+        data: &[T],
+        to_complex: impl Fn(&T) -> Complex<f64> + Sync,
+        params: &PsdParams,
+        sample_rate: f64,
+        one_sided: bool,
+    ) -> (Vec<f64>, Vec<f64>) {
+        compute_psd(
+            data,
+            to_complex,
+            params,
+            sample_rate,
+            one_sided,
+            &AtomicUsize::new(0),
+            &AtomicBool::new(false),
+        )
+        .unwrap()
+    }
+
+    fn peak(freqs: &[f64], psd: &[f64]) -> (f64, f64) {
+        let i = (0..psd.len())
+            .max_by(|&a, &b| psd[a].total_cmp(&psd[b]))
+            .unwrap();
+        (freqs[i], psd[i])
+    }
+
+    #[test]
+    fn complex_tone_power() {
+        // 频点中心上的幅度 0.5 的复指数, 功率谱峰值为 20·log10(0.5)
+        let sample_rate = 1024.;
+        let data: Vec<Complex<f64>> = (0..8192)
+            .map(|n| Complex::from_polar(0.5, 2. * PI * 128. * n as f64 / sample_rate))
+            .collect();
+        let (freqs, psd) = psd_of(&data, |&x| x, &PsdParams::default(), sample_rate, false);
+        let (freq, level) = peak(&freqs, &psd);
+        assert_eq!(freq, 128.);
+        assert!((level - 20. * 0.5f64.log10()).abs() < 1e-6, "{}", level);
+    }
+
+    #[test]
+    fn real_tone_one_sided() {
+        // 幅度为 1 的余弦功率为 1/2, 单边谱上全部集中在正频率
+        let sample_rate = 1024.;
+        let data: Vec<f32> = (0..8192)
+            .map(|n| (2. * PI * 64. * n as f64 / sample_rate).cos() as f32)
+            .collect();
+        let params = PsdParams {
+            window: Window::Hamming,
+            ..Default::default()
+        };
+        let (freqs, psd) = psd_of(
+            &data,
+            |&x| Complex::new(x as f64, 0.),
+            &params,
+            sample_rate,
+            true,
+        );
+        assert_eq!(freqs.len(), params.nfft / 2 + 1);
+        let (freq, level) = peak(&freqs, &psd);
+        assert_eq!(freq, 64.);
+        assert!((level - 10. * 0.5f64.log10()).abs() < 1e-3, "{}", level);
+    }
+
+    #[test]
+    fn density_integrates_to_total_power() {
+        // 功率谱密度乘以频点宽度再求和等于信号的总功率
+        let sample_rate = 48000.;
+        let data: Vec<Complex<f64>> = (0..16384)
+            .map(|n| Complex::from_polar(2., 2. * PI * 1234.5 * n as f64 / sample_rate))
+            .collect();
+        let params = PsdParams {
+            scaling: Scaling::Density,
+            ..Default::default()
+        };
+        let (_, psd) = psd_of(&data, |&x| x, &params, sample_rate, false);
+        let bin_width = sample_rate / params.nfft as f64;
+        let total: f64 = psd.iter().map(|&x| 10f64.powf(x / 10.) * bin_width).sum();
+        assert!((total - 4.).abs() < 0.01, "{}", total);
+    }
 }
@@ -0,0 +1,452 @@
+use crate::dsp::lowpass_taps;
+use crate::fft::Window;
+use std::f64::consts::PI;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum FilterType {
+    LowPass,
+    HighPass,
+    BandPass,
+    BandStop,
+}
+
+impl FilterType {
+    pub const ALL: [FilterType; 4] = [
+        FilterType::LowPass,
+        FilterType::HighPass,
+        FilterType::BandPass,
+        FilterType::BandStop,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterType::LowPass => "Low-pass",
+            FilterType::HighPass => "High-pass",
+            FilterType::BandPass => "Band-pass",
+            FilterType::BandStop => "Band-stop",
+        }
+    }
+
+    /// 带通和带阻需要两个截止频率
+    pub fn two_edges(&self) -> bool {
+        matches!(self, FilterType::BandPass | FilterType::BandStop)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum DesignMethod {
+    WindowedSinc,
+    ParksMcClellan,
+}
+
+impl DesignMethod {
+    pub const ALL: [DesignMethod; 2] = [DesignMethod::WindowedSinc, DesignMethod::ParksMcClellan];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DesignMethod::WindowedSinc => "Windowed sinc",
+            DesignMethod::ParksMcClellan => "Parks-McClellan",
+        }
+    }
+}
+
+/// 滤波器指标, 频率单位为 Hz
+#[derive(PartialEq, Clone, Copy)]
+pub struct FilterSpec {
+    pub filter_type: FilterType,
+    pub method: DesignMethod,
+    /// 截止频率, 低通和高通只用第一个
+    pub cutoff: [f64; 2],
+    /// Parks-McClellan 方法的过渡带宽度, 截止频率位于过渡带中间
+    pub transition: f64,
+    /// 抽头数, 设计时会调整成奇数
+    pub num_taps: usize,
+    pub window: Window,
+}
+
+impl Default for FilterSpec {
+    fn default() -> Self {
+        Self {
+            filter_type: FilterType::LowPass,
+            method: DesignMethod::WindowedSinc,
+            cutoff: [100_000., 200_000.],
+            transition: 20_000.,
+            num_taps: 101,
+            window: Window::Hamming,
+        }
+    }
+}
+
+/// Parks-McClellan 方法的最大抽头数, 再长的设计耗时很久且容易不收敛
+pub const MAX_REMEZ_TAPS: usize = 1023;
+
+/// 按指标设计线性相位 FIR 滤波器
+pub fn design_filter(spec: &FilterSpec, sample_rate: f64) -> Result<Vec<f64>, &'static str> {
+    let num_taps = spec.num_taps.max(3) | 1;
+    let mut f1 = spec.cutoff[0] / sample_rate;
+    let mut f2 = spec.cutoff[1] / sample_rate;
+    if spec.filter_type.two_edges() && f1 > f2 {
+        (f1, f2) = (f2, f1);
+    }
+    let edges_valid = |f: f64| f > 0. && f < 0.5;
+    if !edges_valid(f1) || (spec.filter_type.two_edges() && (!edges_valid(f2) || f1 == f2)) {
+        return Err("Cutoff frequency must be between 0 and half the sample rate!");
+    }
+    match spec.method {
+        DesignMethod::WindowedSinc => Ok(windowed_sinc(
+            spec.filter_type,
+            f1,
+            f2,
+            num_taps,
+            spec.window,
+        )),
+        DesignMethod::ParksMcClellan => {
+            let half = spec.transition / sample_rate / 2.;
+            if half <= 0. {
+                return Err("Transition width must be positive!");
+            }
+            if num_taps > MAX_REMEZ_TAPS {
+                return Err("Too many taps for Parks-McClellan design!");
+            }
+            // (起始频率, 终止频率, 期望增益), 过渡带不参与逼近
+            let bands = match spec.filter_type {
+                FilterType::LowPass => vec![(0., f1 - half, 1.), (f1 + half, 0.5, 0.)],
+                FilterType::HighPass => vec![(0., f1 - half, 0.), (f1 + half, 0.5, 1.)],
+                FilterType::BandPass => vec![
+                    (0., f1 - half, 0.),
+                    (f1 + half, f2 - half, 1.),
+                    (f2 + half, 0.5, 0.),
+                ],
+                FilterType::BandStop => vec![
+                    (0., f1 - half, 1.),
+                    (f1 + half, f2 - half, 0.),
+                    (f2 + half, 0.5, 1.),
+                ],
+            };
+            if bands.iter().any(|&(start, end, _)| start >= end) {
+                return Err("Transition width is too large for the cutoff frequencies!");
+            }
+            remez(num_taps, &bands).ok_or(
+                "Parks-McClellan design did not converge, try fewer taps or a wider transition!",
+            )
+        }
+    }
+}
+
+fn windowed_sinc(
+    filter_type: FilterType,
+    f1: f64,
+    f2: f64,
+    num_taps: usize,
+    window: Window,
+) -> Vec<f64> {
+    // 高通和带阻由全通减去低通和带通得到
+    let invert = |taps: Vec<f64>| {
+        let center = taps.len() / 2;
+        taps.iter()
+            .enumerate()
+            .map(|(i, &tap)| if i == center { 1. - tap } else { -tap })
+            .collect()
+    };
+    let band_pass = || {
+        lowpass_taps(f2, num_taps, window)
+            .iter()
+            .zip(lowpass_taps(f1, num_taps, window))
+            .map(|(a, b)| a - b)
+            .collect::<Vec<f64>>()
+    };
+    match filter_type {
+        FilterType::LowPass => lowpass_taps(f1, num_taps, window),
+        FilterType::HighPass => invert(lowpass_taps(f1, num_taps, window)),
+        FilterType::BandPass => band_pass(),
+        FilterType::BandStop => invert(band_pass()),
+    }
+}
+
+/// 在 0 到 fs/2 之间均匀取 `points` 个频率, 返回每个频率上的幅度响应 (dB)
+pub fn frequency_response(taps: &[f64], points: usize) -> Vec<f64> {
+    (0..points)
+        .map(|k| {
+            let f = k as f64 / (points - 1) as f64 / 2.;
+            let (re, im) = taps
+                .iter()
+                .enumerate()
+                .fold((0., 0.), |(re, im), (n, &tap)| {
+                    let phase = 2. * PI * f * n as f64;
+                    (re + tap * phase.cos(), im - tap * phase.sin())
+                });
+            10. * (re * re + im * im).max(1e-20).log10()
+        })
+        .collect()
+}
+
+/// 频率网格的密度, 每个余弦项对应多少个网格点
+const GRID_DENSITY: usize = 16;
+const MAX_ITERATIONS: usize = 40;
+
+/// Parks-McClellan (Remez 交换) 算法设计奇数长度的对称 FIR 滤波器, 各频带权重相同.
+/// `bands` 中的频率是相对采样率的归一化频率
+fn remez(num_taps: usize, bands: &[(f64, f64, f64)]) -> Option<Vec<f64>> {
+    // 奇数长度的对称滤波器, 幅度响应是 r 个 cos(2πfk) 的线性组合
+    let r = num_taps.div_ceil(2);
+    let total_width: f64 = bands.iter().map(|&(start, end, _)| end - start).sum();
+    let spacing = total_width / (GRID_DENSITY * r) as f64;
+    let mut grid = Vec::new();
+    let mut desired = Vec::new();
+    for &(start, end, gain) in bands {
+        let count = ((end - start) / spacing).ceil().max(1.) as usize;
+        for i in 0..=count {
+            grid.push(start + (end - start) * i as f64 / count as f64);
+            desired.push(gain);
+        }
+    }
+    if grid.len() < r + 1 {
+        return None;
+    }
+    let x: Vec<f64> = grid.iter().map(|&f| (2. * PI * f).cos()).collect();
+    let mut extremals: Vec<usize> = (0..=r).map(|i| i * (grid.len() - 1) / r).collect();
+    let mut interpolate = None;
+    for _ in 0..MAX_ITERATIONS {
+        let ext_x: Vec<f64> = extremals.iter().map(|&i| x[i]).collect();
+        let weights = barycentric_weights(&ext_x);
+        let (mut num, mut den) = (0., 0.);
+        for (k, &i) in extremals.iter().enumerate() {
+            let sign = if k % 2 == 0 { 1. } else { -1. };
+            num += weights[k] * desired[i];
+            den += weights[k] * sign;
+        }
+        let delta = num / den;
+        // 在前 r 个极值点上插值, 第 r+1 个点自动满足
+        let values: Vec<f64> = extremals[..r]
+            .iter()
+            .enumerate()
+            .map(|(k, &i)| desired[i] - if k % 2 == 0 { delta } else { -delta })
+            .collect();
+        let points = ext_x[..r].to_vec();
+        let point_weights = barycentric_weights(&points);
+        let response = move |x: f64| {
+            let (mut num, mut den) = (0., 0.);
+            for k in 0..points.len() {
+                let d = x - points[k];
+                if d.abs() < 1e-12 {
+                    return values[k];
+                }
+                num += point_weights[k] / d * values[k];
+                den += point_weights[k] / d;
+            }
+            num / den
+        };
+        let error: Vec<f64> = (0..grid.len())
+            .map(|i| desired[i] - response(x[i]))
+            .collect();
+        interpolate = Some(response);
+
+        let next = find_extremals(&error, r + 1)?;
+        let max_error = next.iter().map(|&i| error[i].abs()).fold(0., f64::max);
+        let converged = next == extremals || max_error - delta.abs() <= 1e-6 * delta.abs();
+        extremals = next;
+        if converged {
+            break;
+        }
+    }
+    let response = interpolate?;
+    // 在 N 个等间隔频率上采样幅度响应, 逆 DFT 得到冲激响应
+    let n = num_taps as f64;
+    let amplitude: Vec<f64> = (0..r)
+        .map(|k| response((2. * PI * k as f64 / n).cos()))
+        .collect();
+    let center = (num_taps - 1) as f64 / 2.;
+    Some(
+        (0..num_taps)
+            .map(|i| {
+                let t = i as f64 - center;
+                let sum: f64 = amplitude[1..]
+                    .iter()
+                    .enumerate()
+                    .map(|(k, a)| a * (2. * PI * (k + 1) as f64 * t / n).cos())
+                    .sum();
+                (amplitude[0] + 2. * sum) / n
+            })
+            .collect(),
+    )
+}
+
+fn barycentric_weights(x: &[f64]) -> Vec<f64> {
+    // 点很多时连乘会上溢或下溢, 先在对数域计算再整体缩放. 权重只在比值中使用, 缩放不影响结果
+    let logs: Vec<(f64, f64)> = (0..x.len())
+        .map(|k| {
+            (0..x.len())
+                .filter(|&j| j != k)
+                .fold((0., 1.), |(log, sign), j| {
+                    let d = x[k] - x[j];
+                    (log - d.abs().ln(), sign * d.signum())
+                })
+        })
+        .collect();
+    let max = logs.iter().map(|&(log, _)| log).fold(f64::MIN, f64::max);
+    logs.iter()
+        .map(|&(log, sign)| sign * (log - max).exp())
+        .collect()
+}
+
+/// 找出误差曲线上符号交替的 `count` 个极值点
+fn find_extremals(error: &[f64], count: usize) -> Option<Vec<usize>> {
+    let len = error.len();
+    let mut candidates: Vec<usize> = (0..len)
+        .filter(|&i| {
+            let e = error[i].abs();
+            (i == 0 || e >= error[i - 1].abs() || error[i] * error[i - 1] < 0.)
+                && (i + 1 == len || e >= error[i + 1].abs() || error[i] * error[i + 1] < 0.)
+        })
+        .collect();
+    // 相邻同号的极值只保留较大的一个
+    let mut alternating: Vec<usize> = Vec::with_capacity(candidates.len());
+    for i in candidates.drain(..) {
+        match alternating.last() {
+            Some(&last) if error[last].signum() == error[i].signum() => {
+                if error[i].abs() > error[last].abs() {
+                    *alternating.last_mut().unwrap() = i;
+                }
+            }
+            _ => alternating.push(i),
+        }
+    }
+    // 多出来的从两端去掉较小的
+    while alternating.len() > count {
+        if error[alternating[0]].abs() < error[*alternating.last().unwrap()].abs() {
+            alternating.remove(0);
+        } else {
+            alternating.pop();
+        }
+    }
+    (alternating.len() == count).then_some(alternating)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f64 = 1_000_000.;
+
+    /// 在频率 `freq` (Hz) 处的幅度响应
+    fn gain(taps: &[f64], freq: f64) -> f64 {
+        let (re, im) = taps
+            .iter()
+            .enumerate()
+            .fold((0., 0.), |(re, im), (n, &tap)| {
+                let phase = 2. * PI * freq / SAMPLE_RATE * n as f64;
+                (re + tap * phase.cos(), im - tap * phase.sin())
+            });
+        (re * re + im * im).sqrt()
+    }
+
+    /// `start` 到 `end` 之间 (Hz) 与期望增益的最大偏差
+    fn max_error(taps: &[f64], start: f64, end: f64, desired: f64) -> f64 {
+        (0..=200)
+            .map(|i| gain(taps, start + (end - start) * i as f64 / 200.))
+            .map(|g| (g - desired).abs())
+            .fold(0., f64::max)
+    }
+
+    fn spec(filter_type: FilterType, method: DesignMethod, num_taps: usize) -> FilterSpec {
+        FilterSpec {
+            filter_type,
+            method,
+            num_taps,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn windowed_sinc_low_pass_edges() {
+        let taps = design_filter(
+            &spec(FilterType::LowPass, DesignMethod::WindowedSinc, 101),
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        assert_eq!(taps.len(), 101);
+        // 窗函数法的截止频率处增益为一半
+        assert!((gain(&taps, 100_000.) - 0.5).abs() < 0.01);
+        assert!(max_error(&taps, 0., 80_000., 1.) < 0.01);
+        // Hamming 窗的阻带衰减大于 50 dB
+        assert!(max_error(&taps, 125_000., 500_000., 0.) < 10f64.powf(-50. / 20.));
+    }
+
+    #[test]
+    fn windowed_sinc_band_edges() {
+        let band_pass = design_filter(
+            &spec(FilterType::BandPass, DesignMethod::WindowedSinc, 201),
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        let band_stop = design_filter(
+            &spec(FilterType::BandStop, DesignMethod::WindowedSinc, 201),
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        for edge in [100_000., 200_000.] {
+            assert!((gain(&band_pass, edge) - 0.5).abs() < 0.01);
+            assert!((gain(&band_stop, edge) - 0.5).abs() < 0.01);
+        }
+        let stop = 10f64.powf(-50. / 20.);
+        assert!(max_error(&band_pass, 120_000., 180_000., 1.) < 0.01);
+        assert!(max_error(&band_pass, 0., 80_000., 0.) < stop);
+        assert!(max_error(&band_pass, 220_000., 500_000., 0.) < stop);
+        assert!(max_error(&band_stop, 120_000., 180_000., 0.) < stop);
+        assert!(max_error(&band_stop, 0., 80_000., 1.) < 0.01);
+        assert!(max_error(&band_stop, 220_000., 500_000., 1.) < 0.01);
+    }
+
+    #[test]
+    fn remez_low_pass_equiripple() {
+        let taps = design_filter(
+            &spec(FilterType::LowPass, DesignMethod::ParksMcClellan, 101),
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        // 过渡带为 90 kHz 到 110 kHz, 通带和阻带的纹波相同
+        let pass = max_error(&taps, 0., 90_000., 1.);
+        let stop = max_error(&taps, 110_000., 500_000., 0.);
+        assert!(pass < 0.05, "{}", pass);
+        assert!((pass - stop).abs() < 0.1 * pass, "{} {}", pass, stop);
+    }
+
+    #[test]
+    fn remez_high_pass_equiripple() {
+        let taps = design_filter(
+            &spec(FilterType::HighPass, DesignMethod::ParksMcClellan, 101),
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        let stop = max_error(&taps, 0., 90_000., 0.);
+        let pass = max_error(&taps, 110_000., 500_000., 1.);
+        assert!(pass < 0.05, "{}", pass);
+        assert!((pass - stop).abs() < 0.1 * pass, "{} {}", pass, stop);
+    }
+
+    #[test]
+    fn remez_band_pass_equiripple() {
+        let taps = design_filter(
+            &spec(FilterType::BandPass, DesignMethod::ParksMcClellan, 101),
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        let pass = max_error(&taps, 110_000., 190_000., 1.);
+        let stop = max_error(&taps, 0., 90_000., 0.).max(max_error(&taps, 210_000., 500_000., 0.));
+        assert!(pass < 0.1, "{}", pass);
+        assert!((pass - stop).abs() < 0.1 * pass, "{} {}", pass, stop);
+    }
+
+    #[test]
+    fn remez_rejects_too_many_taps() {
+        let result = design_filter(
+            &spec(
+                FilterType::LowPass,
+                DesignMethod::ParksMcClellan,
+                MAX_REMEZ_TAPS + 2,
+            ),
+            SAMPLE_RATE,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::dsp::fir_filter;
+use crate::fft::Window;
+use crate::filter::{
+    design_filter, frequency_response, DesignMethod, FilterSpec, FilterType, MAX_REMEZ_TAPS,
+};
+use crate::series::MultiResolutionSeries;
+use crate::signal_plot::{DerivedTrace, Signal, SignalPlot};
+use crate::utils::{format_freq, human_readable_freq, parse_freq};
+use eframe::egui::{self, Grid, Widget};
+use egui_plot::{Line, PlotPoints};
+use std::sync::Arc;
+use std::thread;
+
+/// 幅度响应的采样点数
+const RESPONSE_POINTS: usize = 1024;
+
+/// 设计得到的系数和幅度响应
+type Design = (Result<Vec<f64>, &'static str>, Vec<f64>);
+
+pub struct FilterDialog {
+    spec: FilterSpec,
+    /// 当前系数 (或正在进行的设计) 对应的指标和采样率, 变化时重新设计
+    designed: Option<(FilterSpec, f64)>,
+    taps: Result<Vec<f64>, &'static str>,
+    response: Vec<f64>,
+    /// Parks-McClellan 设计可能很慢, 在后台进行
+    design_task: Option<thread::JoinHandle<Design>>,
+    task: Option<thread::JoinHandle<DerivedTrace>>,
+}
+
+impl Default for FilterDialog {
+    fn default() -> Self {
+        Self {
+            spec: FilterSpec::default(),
+            designed: None,
+            taps: Ok(Vec::new()),
+            response: Vec::new(),
+            design_task: None,
+            task: None,
+        }
+    }
+}
+
+impl FilterDialog {
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool, signal_plot: &mut SignalPlot) {
+        if self.task.as_ref().is_some_and(|task| task.is_finished()) {
+            if let Ok(trace) = self.task.take().unwrap().join() {
+                signal_plot.add_derived(trace);
+            }
+        }
+        if self
+            .design_task
+            .as_ref()
+            .is_some_and(|task| task.is_finished())
+        {
+            if let Ok((taps, response)) = self.design_task.take().unwrap().join() {
+                self.taps = taps;
+                self.response = response;
+            }
+        }
+        if self.task.is_some() || self.design_task.is_some() {
+            ctx.request_repaint();
+        }
+        let have_signal = signal_plot.have_signal();
+        let sample_rate = signal_plot.sample_rate() as f64;
+        egui::Window::new("Filter")
+            .open(open)
+            .resizable(true)
+            .default_size([400., 400.])
+            .show(ctx, |ui| {
+                Grid::new("filter-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Type");
+                        egui::ComboBox::from_id_salt("filter-type")
+                            .selected_text(self.spec.filter_type.name())
+                            .show_ui(ui, |ui| {
+                                for filter_type in FilterType::ALL {
+                                    ui.selectable_value(
+                                        &mut self.spec.filter_type,
+                                        filter_type,
+                                        filter_type.name(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+                        ui.label("Method");
+                        egui::ComboBox::from_id_salt("filter-method")
+                            .selected_text(self.spec.method.name())
+                            .show_ui(ui, |ui| {
+                                for method in DesignMethod::ALL {
+                                    ui.selectable_value(
+                                        &mut self.spec.method,
+                                        method,
+                                        method.name(),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+                        ui.label("Cutoff")
+                            .on_hover_text("Baseband frequency, relative to the center frequency");
+                        ui.horizontal(|ui| {
+                            let edges = if self.spec.filter_type.two_edges() {
+                                2
+                            } else {
+                                1
+                            };
+                            for cutoff in self.spec.cutoff[..edges].iter_mut() {
+                                freq_value(ui, cutoff, 0., have_signal.then_some(sample_rate));
+                            }
+                        });
+                        ui.end_row();
+                        match self.spec.method {
+                            DesignMethod::WindowedSinc => {
+                                ui.label("Window");
+                                egui::ComboBox::from_id_salt("filter-window")
+                                    .selected_text(self.spec.window.name())
+                                    .show_ui(ui, |ui| {
+                                        // Kaiser 窗需要额外的参数, 这里不提供
+                                        for window in Window::ALL
+                                            .into_iter()
+                                            .filter(|&window| window != Window::Kaiser)
+                                        {
+                                            ui.selectable_value(
+                                                &mut self.spec.window,
+                                                window,
+                                                window.name(),
+                                            );
+                                        }
+                                    });
+                            }
+                            DesignMethod::ParksMcClellan => {
+                                ui.label("Transition");
+                                freq_value(
+                                    ui,
+                                    &mut self.spec.transition,
+                                    1.,
+                                    have_signal.then_some(sample_rate),
+                                );
+                            }
+                        }
+                        ui.end_row();
+                        ui.label("Taps");
+                        let max_taps = match self.spec.method {
+                            DesignMethod::WindowedSinc => 4095,
+                            DesignMethod::ParksMcClellan => MAX_REMEZ_TAPS,
+                        };
+                        egui::DragValue::new(&mut self.spec.num_taps)
+                            .range(3..=max_taps)
+                            .ui(ui);
+                        ui.end_row();
+                    });
+                // 没有信号时采样率没有意义, 不设计
+                if have_signal {
+                    self.redesign(sample_rate);
+                }
+                ui.horizontal(|ui| {
+                    let ready = matches!(&self.taps, Ok(taps) if !taps.is_empty());
+                    if ui
+                        .add_enabled(
+                            ready
+                                && self.design_task.is_none()
+                                && self.task.is_none()
+                                && have_signal,
+                            egui::Button::new("Apply"),
+                        )
+                        .clicked()
+                    {
+                        self.apply(signal_plot);
+                    }
+                    if self.task.is_some() || self.design_task.is_some() {
+                        ui.spinner();
+                    }
+                    if let Err(msg) = &self.taps {
+                        ui.colored_label(egui::Color32::LIGHT_RED, *msg);
+                    }
+                });
+                egui_plot::Plot::new("filter-response")
+                    .x_axis_label("Frequency (baseband)")
+                    .y_axis_label("Magnitude (dB)")
+                    .x_axis_formatter(|mark, _range| human_readable_freq(mark.value))
+                    .show(ui, |plot_ui| {
+                        let step = sample_rate / 2. / (RESPONSE_POINTS - 1) as f64;
+                        plot_ui.line(
+                            Line::new(PlotPoints::new(
+                                self.response
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, &y)| [i as f64 * step, y])
+                                    .collect(),
+                            ))
+                            .name("Response"),
+                        );
+                    });
+            });
+    }
+
+    /// 指标变化时在后台重新设计, 上一次设计完成前不启动新的设计
+    fn redesign(&mut self, sample_rate: f64) {
+        let key = (self.spec, sample_rate);
+        if self.designed == Some(key) || self.design_task.is_some() {
+            return;
+        }
+        self.designed = Some(key);
+        let spec = self.spec;
+        self.design_task = Some(thread::spawn(move || {
+            let taps = design_filter(&spec, sample_rate);
+            let response = match &taps {
+                Ok(taps) => frequency_response(taps, RESPONSE_POINTS),
+                Err(_) => Vec::new(),
+            };
+            (taps, response)
+        }));
+    }
+
+    /// 滤波结果只对当前信号有效, 换信号时丢弃
+    pub fn clear(&mut self) {
+        self.task = None;
+    }
+
+    /// 在后台对整个信号滤波, 结果作为派生曲线
+    fn apply(&mut self, signal_plot: &SignalPlot) {
+        let (Some(signal), Ok(taps)) = (signal_plot.shared_signal(), self.taps.clone()) else {
+            return;
+        };
+        let cutoff = if self.spec.filter_type.two_edges() {
+            format!(
+                "{}-{}",
+                human_readable_freq(self.spec.cutoff[0].min(self.spec.cutoff[1])),
+                human_readable_freq(self.spec.cutoff[0].max(self.spec.cutoff[1]))
+            )
+        } else {
+            human_readable_freq(self.spec.cutoff[0])
+        };
+        let name = format!("{} {}", self.spec.filter_type.name(), cutoff);
+        self.task = Some(thread::spawn(move || {
+            let filtered = match signal.as_ref() {
+                Signal::Real(sig) => {
+                    let data = fir_filter(sig.get(0..sig.len(), 1), &taps);
+                    Signal::Real(MultiResolutionSeries::build(&data, 2048))
+                }
+                Signal::Complex(sig) => {
+                    let data = fir_filter(sig.get(0..sig.len(), 1), &taps);
+                    Signal::Complex(MultiResolutionSeries::build(&data, 2048))
+                }
+            };
+            DerivedTrace {
+                name,
+                signal: Arc::new(filtered),
+                offset: 0.,
+                step: 1.,
+            }
+        }));
+    }
+}
+
+/// 频率输入框, 有信号时限制在 `min` 到奈奎斯特频率之间
+fn freq_value(ui: &mut egui::Ui, value: &mut f64, min: f64, sample_rate: Option<f64>) {
+    let mut drag = egui::DragValue::new(value)
+        .custom_formatter(|f, _range| format_freq(f))
+        .custom_parser(parse_freq);
+    if let Some(sample_rate) = sample_rate {
+        drag = drag
+            .range(min..=sample_rate / 2.)
+            .speed(sample_rate / 1000.);
+    } else {
+        drag = drag.range(min..=f64::MAX).speed(1000.);
+    }
+    drag.ui(ui);
+}
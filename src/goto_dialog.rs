@@ -0,0 +1,108 @@
+use crate::signal_plot::SignalPlot;
+use crate::utils::parse_time;
+use eframe::egui::{self, Align2, Grid, Key, Widget};
+
+#[derive(PartialEq, Clone, Copy)]
+enum GotoUnit {
+    Sample,
+    Time,
+}
+
+pub struct GotoDialog {
+    unit: GotoUnit,
+    position: String,
+    span: String,
+    err_msg: Option<String>,
+}
+
+impl Default for GotoDialog {
+    fn default() -> Self {
+        Self {
+            unit: GotoUnit::Time,
+            position: "".to_owned(),
+            span: "".to_owned(),
+            err_msg: None,
+        }
+    }
+}
+
+impl GotoDialog {
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool, signal_plot: &mut SignalPlot) {
+        let mut done = false;
+        egui::Window::new("Go to")
+            .open(open)
+            .anchor(Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(300.)
+            .show(ctx, |ui| {
+                let enter_press = ui.ctx().input(|i| i.key_pressed(Key::Enter));
+                Grid::new("goto-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Unit");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut self.unit, GotoUnit::Time, "Time");
+                            ui.selectable_value(&mut self.unit, GotoUnit::Sample, "Sample");
+                        });
+                        ui.end_row();
+                        ui.label("Position");
+                        egui::TextEdit::singleline(&mut self.position)
+                            .hint_text(match self.unit {
+                                GotoUnit::Time => "e.g. 1.5ms",
+                                GotoUnit::Sample => "e.g. 120000",
+                            })
+                            .ui(ui);
+                        ui.end_row();
+                        ui.label("Span");
+                        egui::TextEdit::singleline(&mut self.span)
+                            .hint_text("optional")
+                            .ui(ui);
+                        ui.end_row();
+                    });
+                if let Some(msg) = &self.err_msg {
+                    ui.colored_label(ui.visuals().error_fg_color, msg);
+                }
+                ui.add_space(30.);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("Go").clicked() || enter_press {
+                        match self.go(signal_plot) {
+                            Ok(()) => {
+                                self.err_msg = None;
+                                done = true;
+                            }
+                            Err(msg) => self.err_msg = Some(msg.to_owned()),
+                        }
+                    }
+                });
+            });
+        if done {
+            *open = false;
+        }
+    }
+
+    /// 把输入转换为样本序号
+    fn parse(&self, str: &str, sample_rate: f64) -> Option<f64> {
+        match self.unit {
+            GotoUnit::Sample => str.trim().parse::<f64>().ok(),
+            GotoUnit::Time => parse_time(str).map(|t| t * sample_rate),
+        }
+    }
+
+    fn go(&self, signal_plot: &mut SignalPlot) -> Result<(), &str> {
+        let sample_rate = signal_plot.sample_rate() as f64;
+        let position = self
+            .parse(&self.position, sample_rate)
+            .ok_or("Invalid position!")?;
+        let span = if self.span.trim().is_empty() {
+            signal_plot.bounds().width()
+        } else {
+            self.parse(&self.span, sample_rate)
+                .filter(|&span| span > 0.)
+                .ok_or("Invalid span!")?
+        };
+        signal_plot.set_x_range(position, position + span);
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use rayon::prelude::*;
+
+pub struct Histogram {
+    pub centers: Vec<f64>,
+    pub counts: Vec<f64>,
+    pub width: f64,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// `value` 从每个样本中取出要统计的分量
+pub fn compute_histogram<T: Sync>(
+    data: &[T],
+    value: impl Fn(&T) -> f32 + Sync,
+    bins: usize,
+) -> Histogram {
+    if data.is_empty() || bins == 0 {
+        return Histogram {
+            centers: Vec::new(),
+            counts: Vec::new(),
+            width: 0.,
+            min: 0.,
+            max: 0.,
+        };
+    }
+    let (min, max) = data
+        .par_iter()
+        .fold(
+            || (f32::INFINITY, f32::NEG_INFINITY),
+            |(min, max), x| {
+                let x = value(x);
+                (min.min(x), max.max(x))
+            },
+        )
+        .reduce(
+            || (f32::INFINITY, f32::NEG_INFINITY),
+            |a, b| (a.0.min(b.0), a.1.max(b.1)),
+        );
+    // 所有样本相同时给一个非零宽度, 避免除零
+    let span = if max > min { (max - min) as f64 } else { 1.0 };
+    let width = span / bins as f64;
+    let counts = data
+        .par_chunks(65536)
+        .map(|chunk| {
+            let mut counts = vec![0u64; bins];
+            for x in chunk {
+                let i = (((value(x) - min) as f64 / width) as usize).min(bins - 1);
+                counts[i] += 1;
+            }
+            counts
+        })
+        .reduce(
+            || vec![0u64; bins],
+            |mut a, b| {
+                for (ai, bi) in a.iter_mut().zip(b.iter()) {
+                    *ai += *bi;
+                }
+                a
+            },
+        );
+    Histogram {
+        centers: (0..bins)
+            .map(|i| min as f64 + (i as f64 + 0.5) * width)
+            .collect(),
+        counts: counts.into_iter().map(|c| c as f64).collect(),
+        width,
+        min,
+        max,
+    }
+}
@@ -0,0 +1,137 @@
+use crate::histogram::{compute_histogram, Histogram};
+use crate::signal_plot::Signal;
+use eframe::egui::{self, Widget};
+use egui_plot::{Bar, BarChart};
+use std::ops::Range;
+use std::sync::Arc;
+use std::thread;
+
+/// 信号 (以指针区分), 样本范围和分箱数
+type HistogramKey = (usize, Range<usize>, usize);
+type HistogramTask = thread::JoinHandle<Vec<(String, Histogram)>>;
+
+pub struct HistogramDialog {
+    bins: usize,
+    /// 要统计的信号和样本范围
+    source: Option<(Arc<Signal>, Range<usize>)>,
+    /// 当前结果对应的参数
+    key: Option<HistogramKey>,
+    histograms: Vec<(String, Histogram)>,
+    task: Option<(HistogramKey, HistogramTask)>,
+}
+
+impl Default for HistogramDialog {
+    fn default() -> Self {
+        Self {
+            bins: 256,
+            source: None,
+            key: None,
+            histograms: Vec::new(),
+            task: None,
+        }
+    }
+}
+
+impl HistogramDialog {
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        if !*open {
+            return;
+        }
+        self.update(ctx);
+        egui::Window::new("Histogram")
+            .open(open)
+            .resizable(true)
+            .min_size([400.0, 250.0])
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Bins");
+                    egui::DragValue::new(&mut self.bins)
+                        .range(2..=4096)
+                        .speed(1.0)
+                        .ui(ui);
+                    if self.task.is_some() {
+                        ui.spinner();
+                    }
+                });
+                let height = ui.available_height() / self.histograms.len().max(1) as f32;
+                for (name, hist) in self.histograms.iter() {
+                    ui.label(format!("{}: min {} max {}", name, hist.min, hist.max));
+                    let bars = hist
+                        .centers
+                        .iter()
+                        .zip(hist.counts.iter())
+                        .map(|(&x, &y)| Bar::new(x, y).width(hist.width))
+                        .collect();
+                    egui_plot::Plot::new(format!("histogram-{}", name))
+                        .height(height - ui.spacing().interact_size.y)
+                        .x_axis_label("Amplitude")
+                        .y_axis_label("Count")
+                        .show(ui, |plot_ui| {
+                            plot_ui.bar_chart(BarChart::new(bars).name(name));
+                        });
+                }
+            });
+    }
+
+    pub fn set_signal(&mut self, signal: Arc<Signal>, range: Range<usize>) {
+        self.source = Some((signal, range));
+    }
+
+    /// 结果只对当前信号有效, 换信号时清空
+    pub fn clear(&mut self) {
+        self.source = None;
+        self.key = None;
+        self.histograms.clear();
+        self.task = None;
+    }
+
+    /// 参数变化时在后台重新统计, 上一次统计完成前不启动新的统计
+    fn update(&mut self, ctx: &egui::Context) {
+        if let Some((key, task)) = self.task.take() {
+            if !task.is_finished() {
+                self.task = Some((key, task));
+                ctx.request_repaint();
+                return;
+            }
+            if let Ok(histograms) = task.join() {
+                self.key = Some(key);
+                self.histograms = histograms;
+            }
+        }
+        let Some((signal, range)) = &self.source else {
+            return;
+        };
+        let key = (Arc::as_ptr(signal) as usize, range.clone(), self.bins);
+        if self.key.as_ref() == Some(&key) {
+            return;
+        }
+        let (signal, range, bins) = (signal.clone(), range.clone(), self.bins);
+        self.task = Some((
+            key,
+            thread::spawn(move || match signal.as_ref() {
+                Signal::Real(sig) => {
+                    let data = sig.get(range, 1);
+                    vec![(
+                        "amplitude".to_owned(),
+                        compute_histogram(data, |&x| x, bins),
+                    )]
+                }
+                Signal::Complex(sig) => {
+                    let data = sig.get(range, 1);
+                    vec![
+                        (
+                            "inphase".to_owned(),
+                            compute_histogram(data, |x| x.re, bins),
+                        ),
+                        (
+                            "quadrature".to_owned(),
+                            compute_histogram(data, |x| x.im, bins),
+                        ),
+                    ]
+                }
+            }),
+        ));
+        ctx.request_repaint();
+    }
+}
@@ -1,14 +1,44 @@
 #![windows_subsystem = "windows"]
 
+mod annotation;
+mod annotation_panel;
 mod app;
+mod audio;
+mod bookmark_dialog;
+mod burst;
+mod burst_dialog;
+mod cursor_dialog;
+mod cursors;
+mod decoder;
+mod decoder_dialog;
+mod demod;
+mod demod_dialog;
+mod digital;
+mod digital_dialog;
+mod dsp;
 mod export_dialog;
 mod fft;
+mod filter;
+mod filter_dialog;
+mod goto_dialog;
+mod histogram;
+mod histogram_dialog;
 mod menubar;
+mod metadata;
 mod open_dialog;
+mod persistence;
+mod playback_dialog;
 mod psd_dialog;
+mod resample_dialog;
+mod search;
+mod search_dialog;
 mod series;
 mod signal_plot;
+mod spectrum;
+mod stats;
+mod stats_dialog;
 mod utils;
+mod wav;
 
 fn main() -> eframe::Result<()> {
     let icon = include_bytes!("../assets/icon.png");
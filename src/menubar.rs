@@ -125,7 +125,15 @@ impl<T: Clone> MenuBar<T> {
         if self.action.is_some() {
             return self.action.as_ref();
         }
-        for (shortcut, id) in self.shortcuts_map.iter() {
+        // 修饰键多的快捷键优先匹配, 否则 Shift + F3 会被当成 F3
+        let mut shortcuts: Vec<_> = self.shortcuts_map.iter().collect();
+        shortcuts.sort_by_key(|(shortcut, _)| {
+            let m = shortcut.modifiers;
+            std::cmp::Reverse(
+                m.alt as u8 + m.shift as u8 + (m.ctrl || m.command || m.mac_cmd) as u8,
+            )
+        });
+        for (shortcut, id) in shortcuts {
             if ui.ctx().input_mut(|input| input.consume_shortcut(shortcut)) {
                 return Some(id);
             }
@@ -0,0 +1,71 @@
+use crate::open_dialog::SignalType;
+use std::path::Path;
+
+/// 从文件旁边的元数据或者文件名中得到的采集参数
+#[derive(Default)]
+pub struct SignalMetadata {
+    pub sample_rate: Option<f64>,
+    pub center_freq: Option<f64>,
+    pub signal_type: Option<SignalType>,
+}
+
+/// 依次尝试 SigMF 元数据文件和 gqrx 的文件名格式
+pub fn read_metadata(path: &str) -> SignalMetadata {
+    if let Some(metadata) = read_sigmf(path) {
+        return metadata;
+    }
+    parse_gqrx_filename(path).unwrap_or_default()
+}
+
+/// `foo.sigmf-data` 对应的元数据文件为 `foo.sigmf-meta`
+fn read_sigmf(path: &str) -> Option<SignalMetadata> {
+    let meta_path = Path::new(path).with_extension("sigmf-meta");
+    let text = std::fs::read_to_string(meta_path).ok()?;
+    let signal_type =
+        json_string(&text, "core:datatype").and_then(|datatype| match datatype.as_str() {
+            "cf32_le" | "cf32" => Some(SignalType::Complex64),
+            "rf32_le" | "rf32" => Some(SignalType::Float32),
+            _ => None,
+        });
+    Some(SignalMetadata {
+        sample_rate: json_number(&text, "core:sample_rate"),
+        // 只取第一个 capture 的中心频率
+        center_freq: json_number(&text, "core:frequency"),
+        signal_type,
+    })
+}
+
+/// gqrx 录制的文件名形如 `gqrx_20240101_120000_433920000_2000000_fc.raw`
+fn parse_gqrx_filename(path: &str) -> Option<SignalMetadata> {
+    let stem = Path::new(path).file_stem()?.to_str()?;
+    let fields: Vec<&str> = stem.split('_').collect();
+    if fields.len() < 6 || fields[0] != "gqrx" || fields[5] != "fc" {
+        return None;
+    }
+    Some(SignalMetadata {
+        sample_rate: fields[4].parse().ok(),
+        center_freq: fields[3].parse().ok(),
+        signal_type: Some(SignalType::Complex64),
+    })
+}
+
+/// 找到 `"key":` 后面的值的起始位置. 这里只处理 SigMF 需要的简单情况, 不是完整的 JSON 解析
+fn json_value<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("\"{}\"", key);
+    let rest = &text[text.find(&pattern)? + pattern.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?;
+    Some(rest.trim_start())
+}
+
+fn json_number(text: &str, key: &str) -> Option<f64> {
+    let value = json_value(text, key)?;
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+')))
+        .unwrap_or(value.len());
+    value[..end].parse().ok()
+}
+
+fn json_string(text: &str, key: &str) -> Option<String> {
+    let value = json_value(text, key)?.strip_prefix('"')?;
+    Some(value[..value.find('"')?].to_owned())
+}
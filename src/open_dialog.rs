@@ -1,6 +1,7 @@
+use crate::metadata::read_metadata;
 use crate::series::MultiResolutionSeries;
 use crate::signal_plot::Signal;
-use crate::utils::guess_signal_type;
+use crate::utils::{format_freq, guess_signal_type, parse_freq};
 use eframe::egui::{self, Align2, Grid};
 use eframe::egui::{Key, Widget};
 use egui_file_dialog::FileDialog;
@@ -19,6 +20,8 @@ pub enum SignalType {
 pub struct OpenDialog {
     path: String,
     sample_rate: u32,
+    /// 中心频率, 单位 Hz
+    center_freq: f64,
     signal_type: SignalType,
     task: Option<thread::JoinHandle<(Option<Signal>, Option<Signal>)>>,
     file_dialog: FileDialog,
@@ -29,6 +32,7 @@ impl Default for OpenDialog {
         Self {
             path: "".to_owned(),
             sample_rate: 2000000,
+            center_freq: 0.0,
             signal_type: SignalType::Float32,
             task: None,
             file_dialog: FileDialog::new()
@@ -71,7 +75,12 @@ impl OpenDialog {
                             ui.with_layout(
                                 egui::Layout::top_down_justified(egui::Align::LEFT),
                                 |ui| {
-                                    egui::TextEdit::singleline(&mut self.path).ui(ui);
+                                    if egui::TextEdit::singleline(&mut self.path)
+                                        .ui(ui)
+                                        .lost_focus()
+                                    {
+                                        self.apply_metadata();
+                                    }
                                 },
                             );
                         });
@@ -79,44 +88,18 @@ impl OpenDialog {
                         ui.label("Sample Rate");
                         ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
                             egui::DragValue::new(&mut self.sample_rate)
-                                .custom_formatter(|f, _range| {
-                                    if f < 1_000.0 {
-                                        return format!("{:.0} Hz", f);
-                                    }
-                                    if f < 1_000_000.0 {
-                                        return format!("{} kHz", f / 1_000.0);
-                                    }
-                                    if f < 1_000_000_000.0 {
-                                        return format!("{} MHz", f / 1_000_000.0);
-                                    }
-                                    format!("{} GHz", f / 1_000_000_000.0)
-                                })
-                                .custom_parser(|str| {
-                                    // str 1000hz 1000mhz 1000 MHz 10GHz 200 GhZ is valid
-                                    let mut str = str.to_owned();
-                                    str.make_ascii_lowercase();
-                                    let num = str.trim_end_matches(|c: char| {
-                                        c.is_alphabetic() && c != 'e' && c != '.'
-                                    });
-                                    let mut unit = str.trim_start_matches(|c: char| {
-                                        c.is_numeric() || c == '.' || c == 'e'
-                                    });
-                                    if unit.is_empty() {
-                                        unit = "hz";
-                                    }
-                                    let num = num.parse::<f64>();
-                                    if num.is_err() {
-                                        return None;
-                                    }
-                                    let num = num.unwrap();
-                                    match unit {
-                                        "hz" => return Some(num),
-                                        "khz" => return Some(num * 1_000.0),
-                                        "mhz" => return Some(num * 1_000_000.0),
-                                        "ghz" => return Some(num * 1_000_000_000.0),
-                                        _ => return None,
-                                    }
-                                })
+                                .custom_formatter(|f, _range| format_freq(f))
+                                .custom_parser(parse_freq)
+                                .speed(1.0)
+                                .ui(ui);
+                        });
+                        ui.end_row();
+                        ui.label("Center Frequency");
+                        ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
+                            egui::DragValue::new(&mut self.center_freq)
+                                .custom_formatter(|f, _range| format_freq(f))
+                                .custom_parser(parse_freq)
+                                .range(0.0..=f64::MAX)
                                 .speed(1.0)
                                 .ui(ui);
                         });
@@ -162,6 +145,7 @@ impl OpenDialog {
                     if let Some(signal_type) = guess_signal_type(&self.path) {
                         self.signal_type = signal_type;
                     }
+                    self.apply_metadata();
                 }
             });
         if self.task.is_some() && self.task.as_ref().unwrap().is_finished() {
@@ -181,6 +165,24 @@ impl OpenDialog {
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+
+    pub fn center_freq(&self) -> f64 {
+        self.center_freq
+    }
+
+    /// 用 SigMF 元数据或者 gqrx 文件名中的参数填充对话框
+    fn apply_metadata(&mut self) {
+        let metadata = read_metadata(&self.path);
+        if let Some(sample_rate) = metadata.sample_rate {
+            self.sample_rate = sample_rate.round() as u32;
+        }
+        if let Some(center_freq) = metadata.center_freq {
+            self.center_freq = center_freq;
+        }
+        if let Some(signal_type) = metadata.signal_type {
+            self.signal_type = signal_type;
+        }
+    }
 }
 
 fn open_file(path: String, signal_type: SignalType) -> (Option<Signal>, Option<Signal>) {
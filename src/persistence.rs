@@ -0,0 +1,191 @@
+use crate::signal_plot::{Signal, INPHASE_COLOR, MAGNITUDE_COLOR, QUADRATURE_COLOR};
+use eframe::egui::{self, Color32, ColorImage, TextureHandle, TextureOptions};
+use egui_plot::{PlotBounds, PlotImage, PlotPoint, PlotUi};
+use rayon::prelude::*;
+use std::sync::Arc;
+use std::thread;
+
+#[derive(PartialEq, Clone, Copy)]
+struct DensityKey {
+    /// 信号以指针区分, 换信号后即使范围相同也重新统计
+    signal: usize,
+    x: [f64; 2],
+    y: [f64; 2],
+    size: [usize; 2],
+    magnitude: bool,
+}
+
+/// 类似数字荧光示波器的显示: 按原始分辨率统计每个像素上的样本密度, 并用亮度表示
+pub struct PersistenceView {
+    task: Option<thread::JoinHandle<Vec<(String, Color32, ColorImage)>>>,
+    task_key: Option<DensityKey>,
+    key: Option<DensityKey>,
+    textures: Vec<(String, Color32, TextureHandle)>,
+    bounds: PlotBounds,
+}
+
+impl PersistenceView {
+    pub fn new() -> Self {
+        Self {
+            task: None,
+            task_key: None,
+            key: None,
+            textures: Vec::new(),
+            bounds: PlotBounds::from_min_max([0., 0.], [0., 0.]),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        signal: &Arc<Signal>,
+        signal_mag: Option<&Arc<Signal>>,
+        bounds: PlotBounds,
+        size: egui::Vec2,
+        magnitude: bool,
+    ) {
+        if self.task.as_ref().is_some_and(|task| task.is_finished()) {
+            if let Ok(images) = self.task.take().unwrap().join() {
+                self.textures = images
+                    .into_iter()
+                    .map(|(name, color, image)| {
+                        let texture = ctx.load_texture(&name, image, TextureOptions::NEAREST);
+                        (name, color, texture)
+                    })
+                    .collect();
+                self.key = self.task_key;
+                if let Some(key) = self.key {
+                    self.bounds =
+                        PlotBounds::from_min_max([key.x[0], key.y[0]], [key.x[1], key.y[1]]);
+                }
+            }
+        }
+        let key = DensityKey {
+            signal: Arc::as_ptr(signal) as usize,
+            x: [*bounds.range_x().start(), *bounds.range_x().end()],
+            y: [*bounds.range_y().start(), *bounds.range_y().end()],
+            size: [size.x.max(1.) as usize, size.y.max(1.) as usize],
+            magnitude,
+        };
+        if self.task.is_some() {
+            ctx.request_repaint();
+            return;
+        }
+        if self.key == Some(key) {
+            return;
+        }
+        let signal = signal.clone();
+        let signal_mag = signal_mag.cloned();
+        self.task_key = Some(key);
+        self.task = Some(thread::spawn(move || {
+            let mut images = Vec::new();
+            match (signal.as_ref(), signal_mag.as_deref()) {
+                (Signal::Complex(_), Some(Signal::Real(mag))) if magnitude => {
+                    let data = mag.get(0..mag.len(), 1);
+                    let counts = compute_density(data, |&y| y, key);
+                    images.push((
+                        "magnitude".to_owned(),
+                        MAGNITUDE_COLOR,
+                        to_image(&counts, key),
+                    ));
+                }
+                (Signal::Real(sig), _) => {
+                    let data = sig.get(0..sig.len(), 1);
+                    let counts = compute_density(data, |&y| y, key);
+                    images.push(("inphase".to_owned(), INPHASE_COLOR, to_image(&counts, key)));
+                }
+                (Signal::Complex(sig), _) => {
+                    let data = sig.get(0..sig.len(), 1);
+                    let counts = compute_density(data, |y| y.re, key);
+                    images.push(("inphase".to_owned(), INPHASE_COLOR, to_image(&counts, key)));
+                    let counts = compute_density(data, |y| y.im, key);
+                    images.push((
+                        "quadrature".to_owned(),
+                        QUADRATURE_COLOR,
+                        to_image(&counts, key),
+                    ));
+                }
+            }
+            images
+        }));
+        ctx.request_repaint();
+    }
+
+    pub fn is_ready(&self) -> bool {
+        !self.textures.is_empty()
+    }
+
+    pub fn show(&self, plot_ui: &mut PlotUi) {
+        let center = PlotPoint::new(
+            (self.bounds.min()[0] + self.bounds.max()[0]) / 2.,
+            (self.bounds.min()[1] + self.bounds.max()[1]) / 2.,
+        );
+        let size = egui::vec2(self.bounds.width() as f32, self.bounds.height() as f32);
+        for (name, color, texture) in self.textures.iter() {
+            plot_ui.image(
+                PlotImage::new(texture, center, size)
+                    .tint(*color)
+                    .name(name),
+            );
+        }
+    }
+
+    /// 换信号时丢弃旧的结果和进行中的统计
+    pub fn clear(&mut self) {
+        self.task = None;
+        self.task_key = None;
+        self.key = None;
+        self.textures.clear();
+    }
+}
+
+/// 统计每个像素被经过的次数, 相邻样本之间按竖直线段填充. 结果按列存储
+fn compute_density<T: Sync>(
+    data: &[T],
+    value: impl Fn(&T) -> f32 + Sync,
+    key: DensityKey,
+) -> Vec<u32> {
+    let [width, height] = key.size;
+    let [x1, x2] = key.x;
+    let [y1, y2] = key.y;
+    let dx = (x2 - x1) / width as f64;
+    let to_row = |y: f32| ((y2 - y as f64) / (y2 - y1) * height as f64).floor() as isize;
+    let mut counts = vec![0u32; width * height];
+    counts
+        .par_chunks_mut(height)
+        .enumerate()
+        .for_each(|(col, column)| {
+            let start = (x1 + col as f64 * dx).ceil().max(1.) as usize;
+            let end = ((x1 + (col + 1) as f64 * dx).ceil().max(0.) as usize).min(data.len());
+            for i in start..end {
+                let a = to_row(value(&data[i - 1]));
+                let b = to_row(value(&data[i]));
+                let (top, bottom) = (a.min(b), a.max(b));
+                if bottom < 0 || top >= height as isize {
+                    continue;
+                }
+                for row in top.max(0)..=bottom.min(height as isize - 1) {
+                    column[row as usize] += 1;
+                }
+            }
+        });
+    counts
+}
+
+fn to_image(counts: &[u32], key: DensityKey) -> ColorImage {
+    let [width, height] = key.size;
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    let scale = 1. / (1. + max as f32).ln();
+    let mut image = ColorImage::new([width, height], Color32::TRANSPARENT);
+    for col in 0..width {
+        for row in 0..height {
+            let count = counts[col * height + row];
+            if count > 0 {
+                // 对数刻度, 让偶发的毛刺也能看见
+                let intensity = (0.2 + 0.8 * (1. + count as f32).ln() * scale).min(1.);
+                image[(col, row)] = Color32::from_white_alpha((intensity * 255.) as u8);
+            }
+        }
+    }
+    image
+}
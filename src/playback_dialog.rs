@@ -0,0 +1,174 @@
+#[cfg(feature = "audio")]
+use crate::audio::AudioOutput;
+use crate::audio::{render_audio, Audio, RENDER_RATE};
+use crate::demod::AnalogParams;
+use crate::demod_dialog::analog_params_rows;
+use crate::signal_plot::{Signal, SignalPlot};
+use crate::utils::human_readable_time;
+use crate::wav::write_wav;
+use eframe::egui::{self, Grid};
+use egui_file_dialog::FileDialog;
+use std::thread;
+
+/// 音频渲染完成后要做的事
+enum Pending {
+    #[cfg(feature = "audio")]
+    Play,
+    Render(String),
+}
+
+pub struct PlaybackDialog {
+    params: AnalogParams,
+    task: Option<(thread::JoinHandle<Option<Audio>>, Pending)>,
+    /// 正在播放的声卡输出, 音频样本已经交给声卡, 这里只用到位置信息
+    #[cfg(feature = "audio")]
+    playing: Option<(AudioOutput, Audio)>,
+    file_dialog: FileDialog,
+}
+
+impl Default for PlaybackDialog {
+    fn default() -> Self {
+        Self {
+            params: AnalogParams::default(),
+            task: None,
+            #[cfg(feature = "audio")]
+            playing: None,
+            file_dialog: FileDialog::new()
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::new(0., 0.)),
+        }
+    }
+}
+
+impl PlaybackDialog {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        signal_plot: &mut SignalPlot,
+    ) -> Result<(), String> {
+        let mut result = Ok(());
+        if self
+            .task
+            .as_ref()
+            .is_some_and(|(task, _)| task.is_finished())
+        {
+            let (task, pending) = self.task.take().unwrap();
+            result = match (task.join().ok().flatten(), pending) {
+                (None, _) => Err("Cannot resample the signal to the audio rate".to_owned()),
+                (Some(audio), Pending::Render(path)) => {
+                    write_wav(&path, &audio.samples, audio.rate)
+                        .map_err(|err| format!("Failed to render WAV: {}", err))
+                }
+                #[cfg(feature = "audio")]
+                (Some(mut audio), Pending::Play) => {
+                    let samples = std::mem::take(&mut audio.samples);
+                    AudioOutput::play(samples)
+                        .map(|output| {
+                            self.playing = Some((output, audio));
+                        })
+                        .map_err(|err| format!("Failed to play audio: {}", err))
+                }
+            };
+        }
+        if self.task.is_some() {
+            ctx.request_repaint();
+        }
+        #[cfg(feature = "audio")]
+        if let Some((output, audio)) = &self.playing {
+            if let Some(err) = output.take_error() {
+                self.stop(signal_plot);
+                result = Err(format!("Audio stream error: {}", err));
+            } else if output.is_finished() {
+                self.stop(signal_plot);
+            } else {
+                signal_plot.set_playhead(Some(audio.sample_position(output.position())));
+                ctx.request_repaint();
+            }
+        }
+        let is_complex =
+            signal_plot.have_signal() && matches!(signal_plot.signal(), Signal::Complex(_));
+        let range = signal_plot.selected_range();
+        let duration = range.len() as f64 / signal_plot.sample_rate() as f64;
+        egui::Window::new("Playback")
+            .open(open)
+            .resizable(false)
+            .default_width(300.)
+            .show(ctx, |ui| {
+                Grid::new("playback-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Selection");
+                        ui.label(human_readable_time(duration));
+                        ui.end_row();
+                        // 实信号直接播放, 复信号需要先解调
+                        if is_complex {
+                            analog_params_rows(ui, &mut self.params);
+                        }
+                    });
+                ui.add_space(10.);
+                ui.horizontal(|ui| {
+                    let idle = self.task.is_none() && signal_plot.have_signal();
+                    #[cfg(feature = "audio")]
+                    {
+                        if self.playing.is_some() {
+                            if ui.button("Stop").clicked() {
+                                self.stop(signal_plot);
+                            }
+                        } else if ui.add_enabled(idle, egui::Button::new("Play")).clicked() {
+                            match AudioOutput::device_rate() {
+                                Ok(rate) => self.render(signal_plot, rate, Pending::Play),
+                                Err(err) => result = Err(format!("Failed to open audio: {}", err)),
+                            }
+                        }
+                    }
+                    #[cfg(not(feature = "audio"))]
+                    ui.add_enabled(false, egui::Button::new("Play"))
+                        .on_disabled_hover_text("Built without the `audio` feature");
+                    if ui
+                        .add_enabled(idle, egui::Button::new("Render WAV"))
+                        .clicked()
+                    {
+                        self.file_dialog.save_file();
+                    }
+                    if self.task.is_some() {
+                        ui.spinner();
+                    }
+                });
+                self.file_dialog.update(ctx);
+                if let Some(path) = self.file_dialog.take_selected() {
+                    let path = path.to_str().unwrap().to_owned();
+                    self.render(signal_plot, RENDER_RATE, Pending::Render(path));
+                }
+            });
+        result
+    }
+
+    /// 在后台把选中的区间渲染成采样率为 `rate` 的音频
+    fn render(&mut self, signal_plot: &SignalPlot, rate: u32, pending: Pending) {
+        let Some(signal) = signal_plot.shared_signal() else {
+            return;
+        };
+        let range = signal_plot.selected_range();
+        let sample_rate = signal_plot.sample_rate();
+        let params = self.params;
+        let task = thread::spawn(move || render_audio(&signal, range, &params, sample_rate, rate));
+        self.task = Some((task, pending));
+    }
+
+    #[cfg(feature = "audio")]
+    fn stop(&mut self, signal_plot: &mut SignalPlot) {
+        self.playing = None;
+        signal_plot.set_playhead(None);
+    }
+
+    /// 换信号时停止播放, 丢弃还没完成的渲染
+    pub fn clear(&mut self, signal_plot: &mut SignalPlot) {
+        self.task = None;
+        #[cfg(feature = "audio")]
+        {
+            self.playing = None;
+        }
+        signal_plot.set_playhead(None);
+    }
+}
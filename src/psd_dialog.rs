@@ -1,50 +1,501 @@
-use eframe::egui;
-use egui_plot::{Line, PlotPoints};
+use crate::dsp::downconvert;
+use crate::fft::{compute_psd, segment_count, Averaging, PsdParams, Scaling, Window};
+use crate::series::MultiResolutionSeries;
+use crate::signal_plot::{DerivedTrace, Signal};
+use crate::spectrum::{find_peaks, level_at, measure_channel, SpectrumMarkers};
+use crate::utils::human_readable_freq;
+use eframe::egui::{self, Grid, Widget};
+use egui_plot::{Line, PlotPoints, VLine};
 use emath::vec2;
+use rustfft::num_complex::Complex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+struct PsdTask {
+    handle: thread::JoinHandle<Option<(Vec<f64>, Vec<f64>)>>,
+    /// 已经处理的段数和总段数
+    progress: Arc<AtomicUsize>,
+    total: usize,
+    cancel: Arc<AtomicBool>,
+}
 
 pub struct PsdDialog {
+    signal: Option<Arc<Signal>>,
+    range: std::ops::Range<usize>,
+    sample_rate: f64,
+    center_freq: f64,
+    /// 频率轴显示绝对射频频率, 否则显示相对中心频率的基带偏移
+    absolute_freq: bool,
+    task: Option<PsdTask>,
+    params: PsdParams,
     freqs: Vec<f64>,
     psd: Vec<f64>,
+    markers: SpectrumMarkers,
+    /// 按住 R 拖动选择的信道范围, 基带频率
+    span: Option<[f64; 2]>,
+    span_active: bool,
+    /// 把选中频带下变频成派生曲线的后台任务
+    band_task: Option<thread::JoinHandle<DerivedTrace>>,
+    peak_count: usize,
+    /// 峰值搜索的门限, 单位和纵轴相同
+    peak_threshold: f64,
 }
 
 impl Default for PsdDialog {
     fn default() -> Self {
         Self {
+            signal: None,
+            range: 0..0,
+            sample_rate: 1.0,
+            center_freq: 0.0,
+            absolute_freq: true,
+            task: None,
+            params: PsdParams::default(),
             freqs: Vec::new(),
             psd: Vec::new(),
+            markers: SpectrumMarkers::new(),
+            span: None,
+            span_active: false,
+            band_task: None,
+            peak_count: 5,
+            peak_threshold: -100.0,
         }
     }
 }
 
 impl PsdDialog {
-    pub fn show(&self, ctx: &egui::Context, open: &mut bool) {
+    /// 选中的频带下变频完成后返回得到的派生曲线
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) -> Option<DerivedTrace> {
+        let mut derived = None;
+        if self
+            .band_task
+            .as_ref()
+            .is_some_and(|task| task.is_finished())
+        {
+            derived = self.band_task.take().unwrap().join().ok();
+        }
+        if self
+            .task
+            .as_ref()
+            .is_some_and(|task| task.handle.is_finished())
+        {
+            if let Ok(Some((freqs, psd))) = self.task.take().unwrap().handle.join() {
+                self.freqs = freqs;
+                self.psd = psd;
+            }
+        }
+        if self.task.is_some() || self.band_task.is_some() {
+            ctx.request_repaint();
+        }
         egui::Window::new("PSD")
             .open(open)
             .resizable(true)
             .min_size([400.0, 250.0])
             .default_size([400.0, 250.0])
             .show(ctx, |ui| {
+                let params = self.params;
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("NFFT");
+                    egui::ComboBox::from_id_salt("psd-nfft")
+                        .selected_text(format!("{}", self.params.nfft))
+                        .width(70.)
+                        .show_ui(ui, |ui| {
+                            for nfft in (6..=16).map(|i| 1 << i) {
+                                ui.selectable_value(
+                                    &mut self.params.nfft,
+                                    nfft,
+                                    format!("{}", nfft),
+                                );
+                            }
+                        });
+                    ui.label("Window");
+                    egui::ComboBox::from_id_salt("psd-window")
+                        .selected_text(self.params.window.name())
+                        .show_ui(ui, |ui| {
+                            for window in Window::ALL {
+                                ui.selectable_value(&mut self.params.window, window, window.name());
+                            }
+                        });
+                    if self.params.window == Window::Kaiser {
+                        egui::DragValue::new(&mut self.params.kaiser_beta)
+                            .prefix("β ")
+                            .range(0.0..=30.0)
+                            .speed(0.1)
+                            .ui(ui);
+                    }
+                    ui.label("Overlap");
+                    let mut overlap = self.params.overlap * 100.;
+                    if egui::DragValue::new(&mut overlap)
+                        .range(0.0..=95.0)
+                        .suffix("%")
+                        .ui(ui)
+                        .changed()
+                    {
+                        self.params.overlap = overlap / 100.;
+                    }
+                    ui.label("Averaging");
+                    egui::ComboBox::from_id_salt("psd-averaging")
+                        .selected_text(self.params.averaging.name())
+                        .show_ui(ui, |ui| {
+                            for averaging in Averaging::ALL {
+                                ui.selectable_value(
+                                    &mut self.params.averaging,
+                                    averaging,
+                                    averaging.name(),
+                                );
+                            }
+                        });
+                    ui.label("Scaling");
+                    egui::ComboBox::from_id_salt("psd-scaling")
+                        .selected_text(self.params.scaling.name())
+                        .show_ui(ui, |ui| {
+                            for scaling in Scaling::ALL {
+                                ui.selectable_value(
+                                    &mut self.params.scaling,
+                                    scaling,
+                                    scaling.name(),
+                                );
+                            }
+                        });
+                    ui.checkbox(&mut self.absolute_freq, "Absolute RF");
+                });
+                if params != self.params {
+                    self.update();
+                }
+                if let Some(task) = &self.task {
+                    let progress = task.progress.load(Ordering::Relaxed) as f32 / task.total as f32;
+                    egui::ProgressBar::new(progress).show_percentage().ui(ui);
+                }
+                let offset = self.freq_offset();
+                let unit = self.params.scaling.unit();
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Peaks");
+                    egui::DragValue::new(&mut self.peak_count)
+                        .range(1..=20)
+                        .ui(ui);
+                    ui.label("Threshold");
+                    egui::DragValue::new(&mut self.peak_threshold)
+                        .suffix(format!(" {}", unit))
+                        .speed(0.5)
+                        .ui(ui);
+                    if ui.button("Find Peaks").clicked() {
+                        self.markers.freqs =
+                            find_peaks(&self.psd, self.peak_threshold, self.peak_count)
+                                .into_iter()
+                                .map(|i| self.freqs[i])
+                                .collect();
+                    }
+                    if ui.button("Clear Markers").clicked() {
+                        self.markers.clear();
+                    }
+                });
+                if !self.markers.freqs.is_empty() {
+                    self.marker_table(ui, offset, unit);
+                }
+                self.channel_table(ui, offset, unit);
+                if self.span.is_some() {
+                    ui.horizontal(|ui| {
+                        let button = ui.add_enabled(
+                            self.band_task.is_none(),
+                            egui::Button::new("Extract Band"),
+                        );
+                        if button
+                            .on_hover_text(
+                                "Shift the selected band to baseband, filter and decimate it",
+                            )
+                            .clicked()
+                        {
+                            self.extract_band(offset);
+                        }
+                        if self.band_task.is_some() {
+                            ui.spinner();
+                        }
+                    });
+                }
+                let r_pressed = ui.ctx().input(|i| i.key_down(egui::Key::R));
                 egui_plot::Plot::new("psd")
                     .set_margin_fraction(vec2(0., 0.1))
-                    .x_axis_label("Frequency")
-                    .y_axis_label("Power Spectral Density (dB)")
+                    .x_axis_label(freq_label(offset))
+                    .x_axis_formatter(|mark, _range| human_readable_freq(mark.value))
+                    .allow_drag(!self.markers.grabbed() && !r_pressed)
+                    .allow_double_click_reset(false)
+                    .y_axis_label(format!("{} ({})", self.params.scaling.name(), unit))
+                    .label_formatter(move |_name, point| {
+                        format!("{}\n{:.2} {}", human_readable_freq(point.x), point.y, unit)
+                    })
                     .show(ui, |plot_ui| {
                         let line = Line::new(PlotPoints::new(
                             self.freqs
                                 .iter()
                                 .zip(self.psd.iter())
-                                .map(|(&x, &y)| [x, y])
+                                .map(|(&x, &y)| [x + offset, y])
                                 .collect(),
                         ))
                         .fill(-1000.)
                         .name("PSD");
                         plot_ui.line(line);
+                        self.update_span(plot_ui, offset, r_pressed);
+                        self.markers.update(plot_ui, offset);
+                        self.markers.draw(plot_ui, &self.freqs, &self.psd, offset);
                     });
             });
+        derived
+    }
+
+    pub fn set_signal(
+        &mut self,
+        signal: Arc<Signal>,
+        range: std::ops::Range<usize>,
+        sample_rate: f64,
+        center_freq: f64,
+    ) {
+        self.signal = Some(signal);
+        self.range = range;
+        self.sample_rate = sample_rate;
+        self.center_freq = center_freq;
+        self.update();
+    }
+
+    /// 换信号时清空, 取消还在进行的计算, 旧信号上的频带也不能再提取
+    pub fn clear(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.cancel.store(true, Ordering::Relaxed);
+        }
+        self.band_task = None;
+        self.signal = None;
+        self.span = None;
+        self.freqs.clear();
+        self.psd.clear();
+        self.markers.clear();
+    }
+
+    /// 列出每个标记的频率和电平, 以及相对第一个标记的差值
+    fn marker_table(&mut self, ui: &mut egui::Ui, offset: f64, unit: &str) {
+        let mut removed = None;
+        let reference = level_at(&self.freqs, &self.psd, self.markers.freqs[0]);
+        Grid::new("psd-markers")
+            .num_columns(6)
+            .striped(true)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.strong("Marker");
+                ui.strong(freq_label(offset));
+                ui.strong("Level");
+                ui.strong("ΔFrequency");
+                ui.strong("ΔLevel");
+                ui.end_row();
+                for (i, &freq) in self.markers.freqs.iter().enumerate() {
+                    let Some((freq, level)) = level_at(&self.freqs, &self.psd, freq) else {
+                        break;
+                    };
+                    ui.label(format!("M{}", i + 1));
+                    ui.label(human_readable_freq(freq + offset));
+                    ui.label(format!("{:.2} {}", level, unit));
+                    match reference {
+                        Some((ref_freq, ref_level)) if i > 0 => {
+                            ui.label(human_readable_freq(freq - ref_freq));
+                            ui.label(format!("{:.2} dB", level - ref_level));
+                        }
+                        _ => {
+                            ui.label("-");
+                            ui.label("-");
+                        }
+                    }
+                    if ui.small_button("✖").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = removed {
+            self.markers.remove(i);
+        }
+    }
+
+    /// 按住 R 拖动选择信道范围, 按住 R 单击清除
+    fn update_span(&mut self, plot_ui: &mut egui_plot::PlotUi, offset: f64, r_pressed: bool) {
+        let response = plot_ui.response().clone();
+        if r_pressed && response.clicked_by(egui::PointerButton::Primary) {
+            self.span = None;
+        }
+        if r_pressed && response.drag_started_by(egui::PointerButton::Primary) {
+            if let Some(pointer) = plot_ui.pointer_coordinate() {
+                self.span = Some([pointer.x - offset; 2]);
+                self.span_active = true;
+            }
+        }
+        if response.drag_stopped_by(egui::PointerButton::Primary) {
+            self.span_active = false;
+        }
+        if let (true, Some(span), Some(pointer)) = (
+            self.span_active,
+            self.span.as_mut(),
+            plot_ui.pointer_coordinate(),
+        ) {
+            span[1] = pointer.x - offset;
+        }
+        if let Some(span) = self.span {
+            // 不能用覆盖整个纵轴的多边形, 否则自动缩放会一帧一帧地扩大范围
+            for x in span {
+                plot_ui.vline(
+                    VLine::new(x + offset)
+                        .color(egui::Color32::WHITE)
+                        .width(2.)
+                        .name("Channel"),
+                );
+            }
+        }
+    }
+
+    /// 显示选中信道的功率, 占用带宽, 邻道功率比和信噪比
+    fn channel_table(&self, ui: &mut egui::Ui, offset: f64, unit: &str) {
+        let Some(span) = self.span else {
+            return;
+        };
+        if self.freqs.len() < 2 {
+            return;
+        }
+        let bin_scale = match self.params.scaling {
+            Scaling::PowerSpectrum => 1. / self.params.enbw_bins(),
+            Scaling::Density => self.freqs[1] - self.freqs[0],
+        };
+        let Some(result) = measure_channel(&self.freqs, &self.psd, span, bin_scale) else {
+            return;
+        };
+        let format_acpr = |acpr: Option<f64>| match acpr {
+            Some(acpr) => format!("{:.2} dBc", acpr),
+            None => "-".to_owned(),
+        };
+        Grid::new("psd-channel")
+            .num_columns(4)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Channel");
+                ui.label(format!(
+                    "{} - {}",
+                    human_readable_freq(span[0].min(span[1]) + offset),
+                    human_readable_freq(span[0].max(span[1]) + offset)
+                ));
+                ui.label("Power");
+                ui.label(format!("{:.2} dB", result.power));
+                ui.end_row();
+                ui.label("99% OBW");
+                ui.label(human_readable_freq(result.occupied_bandwidth));
+                ui.label("SNR");
+                ui.label(format!("{:.2} dB", result.snr));
+                ui.end_row();
+                ui.label("ACPR lower");
+                ui.label(format_acpr(result.acpr_lower));
+                ui.label("ACPR upper");
+                ui.label(format_acpr(result.acpr_upper));
+                ui.end_row();
+                ui.label("Noise floor");
+                ui.label(format!("{:.2} {}", result.noise_floor, unit));
+                ui.end_row();
+            });
+    }
+
+    /// 在后台把选中的频带搬到基带, 滤波并抽取
+    fn extract_band(&mut self, offset: f64) {
+        let (Some(signal), Some(span)) = (self.signal.clone(), self.span) else {
+            return;
+        };
+        let center = (span[0] + span[1]) / 2.;
+        let bandwidth = (span[1] - span[0]).abs();
+        if bandwidth <= 0. {
+            return;
+        }
+        let range = self.range.clone();
+        let sample_rate = self.sample_rate;
+        let name = format!(
+            "{} @ {}",
+            human_readable_freq(bandwidth),
+            human_readable_freq(center + offset)
+        );
+        self.band_task = Some(thread::spawn(move || {
+            let (data, decimation) = match signal.as_ref() {
+                Signal::Real(sig) => downconvert(
+                    sig.get(range.clone(), 1),
+                    |&x| Complex::new(x as f64, 0.0),
+                    center,
+                    bandwidth,
+                    sample_rate,
+                ),
+                Signal::Complex(sig) => downconvert(
+                    sig.get(range.clone(), 1),
+                    |x| Complex::new(x.re as f64, x.im as f64),
+                    center,
+                    bandwidth,
+                    sample_rate,
+                ),
+            };
+            DerivedTrace {
+                name,
+                signal: Arc::new(Signal::Complex(MultiResolutionSeries::build(&data, 2048))),
+                offset: range.start as f64,
+                step: decimation as f64,
+            }
+        }));
+    }
+
+    /// 频率轴上需要加上的偏移
+    fn freq_offset(&self) -> f64 {
+        if self.absolute_freq {
+            self.center_freq
+        } else {
+            0.0
+        }
     }
 
-    pub fn set_data(&mut self, freqs: Vec<f64>, psd: Vec<f64>) {
-        self.freqs = freqs;
-        self.psd = psd;
+    /// 取消正在进行的计算, 在后台线程重新计算
+    fn update(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.cancel.store(true, Ordering::Relaxed);
+        }
+        let Some(signal) = self.signal.clone() else {
+            return;
+        };
+        let range = self.range.clone();
+        let params = self.params;
+        let sample_rate = self.sample_rate;
+        let progress = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (task_progress, task_cancel) = (progress.clone(), cancel.clone());
+        let handle = thread::spawn(move || match signal.as_ref() {
+            Signal::Real(sig) => compute_psd(
+                sig.get(range, 1),
+                |&x| Complex::new(x as f64, 0.0),
+                &params,
+                sample_rate,
+                true,
+                &task_progress,
+                &task_cancel,
+            ),
+            Signal::Complex(sig) => compute_psd(
+                sig.get(range, 1),
+                |x| Complex::new(x.re as f64, x.im as f64),
+                &params,
+                sample_rate,
+                false,
+                &task_progress,
+                &task_cancel,
+            ),
+        });
+        self.task = Some(PsdTask {
+            handle,
+            progress,
+            total: segment_count(self.range.len(), &self.params),
+            cancel,
+        });
+    }
+}
+
+/// 频率轴的名称, 没有加上中心频率时标明是基带频率
+fn freq_label(offset: f64) -> &'static str {
+    if offset != 0. {
+        "Frequency (RF)"
+    } else {
+        "Frequency (baseband)"
     }
 }
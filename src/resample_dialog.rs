@@ -0,0 +1,183 @@
+use crate::dsp::{frequency_shift, rational_factors, resample, MAX_FACTOR};
+use crate::series::MultiResolutionSeries;
+use crate::signal_plot::{Signal, SignalPlot};
+use crate::utils::{format_freq, parse_freq};
+use eframe::egui::{self, Grid, Widget};
+use rustfft::num_complex::Complex;
+use std::thread;
+
+/// 频移和重采样的结果, 替换当前信号
+pub struct ResampleResult {
+    pub signal: Signal,
+    pub signal_mag: Option<Signal>,
+    pub sample_rate: u32,
+    /// 频移量, 单位 Hz
+    pub shift: f64,
+    /// 输出样本数和输入样本数之比
+    pub ratio: f64,
+}
+
+pub struct ResampleDialog {
+    shift: f64,
+    sample_rate: u32,
+    task: Option<thread::JoinHandle<ResampleResult>>,
+}
+
+impl Default for ResampleDialog {
+    fn default() -> Self {
+        Self {
+            shift: 0.0,
+            sample_rate: 250000,
+            task: None,
+        }
+    }
+}
+
+impl ResampleDialog {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        signal_plot: &SignalPlot,
+    ) -> Option<ResampleResult> {
+        let mut result = None;
+        if self.task.as_ref().is_some_and(|task| task.is_finished()) {
+            result = self.task.take().unwrap().join().ok();
+            *open = false;
+        }
+        if self.task.is_some() {
+            ctx.request_repaint();
+        }
+        let input_rate = signal_plot.sample_rate();
+        let factors = rational_factors(input_rate, self.sample_rate);
+        egui::Window::new("Shift / Resample")
+            .open(open)
+            .resizable(false)
+            .default_width(300.)
+            .show(ctx, |ui| {
+                Grid::new("resample-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Frequency Shift").on_hover_text(
+                            "Moves the baseband spectrum, a component at -shift ends up at 0 Hz",
+                        );
+                        egui::DragValue::new(&mut self.shift)
+                            .custom_formatter(|f, _range| format_freq(f))
+                            .custom_parser(parse_freq)
+                            .speed(100.0)
+                            .ui(ui);
+                        ui.end_row();
+                        ui.label("Input Sample Rate");
+                        ui.label(format_freq(input_rate as f64));
+                        ui.end_row();
+                        ui.label("Output Sample Rate");
+                        egui::DragValue::new(&mut self.sample_rate)
+                            .custom_formatter(|f, _range| format_freq(f))
+                            .custom_parser(parse_freq)
+                            .range(1..=u32::MAX)
+                            .speed(1.0)
+                            .ui(ui);
+                        ui.end_row();
+                        ui.label("Ratio");
+                        match factors {
+                            Some((up, down)) => ui.label(format!("{} / {}", up, down)),
+                            None => ui
+                                .colored_label(egui::Color32::LIGHT_RED, "Too complex")
+                                .on_hover_text(format!(
+                                    "Interpolation and decimation factors must be at most {}",
+                                    MAX_FACTOR
+                                )),
+                        };
+                        ui.end_row();
+                    });
+                ui.add_space(10.);
+                ui.horizontal(|ui| {
+                    let enabled =
+                        factors.is_some() && self.task.is_none() && signal_plot.have_signal();
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Apply"))
+                        .on_hover_text(
+                            "Replace the current signal with the result, \
+                             annotation changes are no longer saved to the file",
+                        )
+                        .clicked()
+                    {
+                        if let Some((up, down)) = factors {
+                            self.apply(signal_plot, up, down);
+                        }
+                    }
+                    if self.task.is_some() {
+                        ui.spinner();
+                    }
+                });
+            });
+        result
+    }
+
+    /// 结果只对当前信号有效, 换信号时丢弃
+    pub fn clear(&mut self) {
+        self.task = None;
+    }
+
+    /// 在后台对整个信号频移和重采样
+    fn apply(&mut self, signal_plot: &SignalPlot, up: usize, down: usize) {
+        let Some(signal) = signal_plot.shared_signal() else {
+            return;
+        };
+        let shift = self.shift;
+        let input_rate = signal_plot.sample_rate() as f64;
+        let sample_rate = self.sample_rate;
+        self.task = Some(thread::spawn(move || {
+            let (signal, signal_mag) = match signal.as_ref() {
+                // 不需要频移的实信号保持为实信号
+                Signal::Real(sig) if shift == 0. => {
+                    let data = resample(sig.get(0..sig.len(), 1), up, down);
+                    (
+                        Signal::Real(MultiResolutionSeries::build(&data, 2048)),
+                        None,
+                    )
+                }
+                Signal::Real(sig) => {
+                    let shifted = frequency_shift(
+                        sig.get(0..sig.len(), 1),
+                        |&x| Complex::new(x as f64, 0.),
+                        shift,
+                        input_rate,
+                    );
+                    complex_signal(&resample(&shifted, up, down))
+                }
+                Signal::Complex(sig) => {
+                    let data = sig.get(0..sig.len(), 1);
+                    let data = if shift == 0. {
+                        resample(data, up, down)
+                    } else {
+                        let shifted = frequency_shift(
+                            data,
+                            |x| Complex::new(x.re as f64, x.im as f64),
+                            shift,
+                            input_rate,
+                        );
+                        resample(&shifted, up, down)
+                    };
+                    complex_signal(&data)
+                }
+            };
+            ResampleResult {
+                signal,
+                signal_mag,
+                sample_rate,
+                shift,
+                ratio: up as f64 / down as f64,
+            }
+        }));
+    }
+}
+
+fn complex_signal(data: &[Complex<f32>]) -> (Signal, Option<Signal>) {
+    let mag = data.iter().map(|x| x.norm()).collect::<Vec<f32>>();
+    (
+        Signal::Complex(MultiResolutionSeries::build(data, 2048)),
+        Some(Signal::Real(MultiResolutionSeries::build(&mag, 2048))),
+    )
+}
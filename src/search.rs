@@ -0,0 +1,107 @@
+use crate::series::{Downconvert, MultiResolutionSeries};
+
+/// 在金字塔上每次跳过的块的大小 (原始样本数的一半)
+const SEARCH_RATIO: usize = 1024;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Either,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// 查找 `value` 穿过 `level` 的位置, 返回穿越后的第一个样本.
+/// 利用 min/max 金字塔整块跳过完全在阈值一侧的数据, 只在可能穿越的块里逐个样本检查
+pub fn find_edge<T: Downconvert<T> + Clone>(
+    series: &MultiResolutionSeries<T>,
+    value: impl Fn(&T) -> f32,
+    level: f32,
+    edge: Edge,
+    from: usize,
+    direction: Direction,
+) -> Option<usize> {
+    let len = series.len();
+    let data = series.get(0..len, 1);
+    let ratio = SEARCH_RATIO.min(series.max_ratio());
+    let pyramid = series.get(0..len, ratio);
+    let block = 2 * ratio;
+    let blocks = (pyramid.len() / 2).min(len / block);
+    let above = |x: &T| value(x) >= level;
+    // 整块都在阈值同一侧时返回这一侧
+    let block_side = |b: usize| {
+        let max = value(&pyramid[2 * b]);
+        let min = value(&pyramid[2 * b + 1]);
+        if min >= level {
+            Some(true)
+        } else if max < level {
+            Some(false)
+        } else {
+            None
+        }
+    };
+    let is_edge = |before: bool, after: bool| match edge {
+        Edge::Rising => !before && after,
+        Edge::Falling => before && !after,
+        Edge::Either => before != after,
+    };
+    match direction {
+        Direction::Forward => {
+            if from + 1 >= len {
+                return None;
+            }
+            let mut prev = above(&data[from]);
+            let mut i = from + 1;
+            while i < len {
+                if ratio > 1 && i.is_multiple_of(block) && i / block < blocks {
+                    if let Some(side) = block_side(i / block) {
+                        if is_edge(prev, side) {
+                            return Some(i);
+                        }
+                        prev = side;
+                        i += block;
+                        continue;
+                    }
+                }
+                let cur = above(&data[i]);
+                if is_edge(prev, cur) {
+                    return Some(i);
+                }
+                prev = cur;
+                i += 1;
+            }
+        }
+        Direction::Backward => {
+            if from < 2 || from > len {
+                return None;
+            }
+            // 检查样本 i - 1 和 i 之间是否穿越
+            let mut i = from - 1;
+            let mut next = above(&data[i]);
+            while i >= 1 {
+                if ratio > 1 && i.is_multiple_of(block) && i >= block && i / block - 1 < blocks {
+                    if let Some(side) = block_side(i / block - 1) {
+                        if is_edge(side, next) {
+                            return Some(i);
+                        }
+                        next = side;
+                        i -= block;
+                        continue;
+                    }
+                }
+                let cur = above(&data[i - 1]);
+                if is_edge(cur, next) {
+                    return Some(i);
+                }
+                next = cur;
+                i -= 1;
+            }
+        }
+    }
+    None
+}
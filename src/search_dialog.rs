@@ -0,0 +1,147 @@
+use crate::search::{find_edge, Direction, Edge};
+use crate::signal_plot::{Signal, SignalPlot};
+use crate::utils::human_readable_time;
+use eframe::egui::{self, Grid, Widget};
+
+#[derive(PartialEq, Clone, Copy)]
+enum Trace {
+    Inphase,
+    Quadrature,
+    Magnitude,
+}
+
+pub struct SearchDialog {
+    trace: Trace,
+    level: f32,
+    edge: Edge,
+    found: Option<usize>,
+    err_msg: Option<String>,
+}
+
+impl Default for SearchDialog {
+    fn default() -> Self {
+        Self {
+            trace: Trace::Inphase,
+            level: 0.5,
+            edge: Edge::Rising,
+            found: None,
+            err_msg: None,
+        }
+    }
+}
+
+impl SearchDialog {
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool, signal_plot: &mut SignalPlot) {
+        let complex = matches!(signal_plot.signal(), Signal::Complex(_));
+        let sample_rate = signal_plot.sample_rate() as f64;
+        egui::Window::new("Find Edge")
+            .open(open)
+            .resizable(false)
+            .default_width(250.)
+            .show(ctx, |ui| {
+                Grid::new("search-options")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Trace");
+                        if complex {
+                            egui::ComboBox::from_id_salt("search-trace")
+                                .selected_text(match self.trace {
+                                    Trace::Inphase => "inphase",
+                                    Trace::Quadrature => "quadrature",
+                                    Trace::Magnitude => "magnitude",
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.trace, Trace::Inphase, "inphase");
+                                    ui.selectable_value(
+                                        &mut self.trace,
+                                        Trace::Quadrature,
+                                        "quadrature",
+                                    );
+                                    ui.selectable_value(
+                                        &mut self.trace,
+                                        Trace::Magnitude,
+                                        "magnitude",
+                                    );
+                                });
+                        } else {
+                            ui.label("real");
+                        }
+                        ui.end_row();
+                        ui.label("Level");
+                        egui::DragValue::new(&mut self.level).speed(0.01).ui(ui);
+                        ui.end_row();
+                        ui.label("Edge");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut self.edge, Edge::Rising, "Rising");
+                            ui.selectable_value(&mut self.edge, Edge::Falling, "Falling");
+                            ui.selectable_value(&mut self.edge, Edge::Either, "Either");
+                        });
+                        ui.end_row();
+                    });
+                if let Some(msg) = &self.err_msg {
+                    ui.colored_label(ui.visuals().error_fg_color, msg);
+                } else if let Some(found) = self.found {
+                    ui.label(format!(
+                        "Found at sample {} ({})",
+                        found,
+                        human_readable_time(found as f64 / sample_rate)
+                    ));
+                }
+                ui.add_space(10.);
+                ui.horizontal(|ui| {
+                    if ui.button("Previous").clicked() {
+                        self.find(signal_plot, Direction::Backward);
+                    }
+                    if ui.button("Next").clicked() {
+                        self.find(signal_plot, Direction::Forward);
+                    }
+                });
+            });
+    }
+
+    /// 找到的位置只对当前信号有效, 换信号时清空
+    pub fn clear(&mut self) {
+        self.found = None;
+        self.err_msg = None;
+    }
+
+    /// 从当前视图的中心开始查找, 找到后把视图中心移到找到的位置
+    pub fn find(&mut self, signal_plot: &mut SignalPlot, direction: Direction) {
+        if !signal_plot.have_signal() {
+            return;
+        }
+        let bounds = signal_plot.bounds();
+        let center = (bounds.min()[0] + bounds.max()[0]) / 2.;
+        // 视图中心可能在信号之外, 向后查找时从信号末尾开始
+        let from = (center.round().max(0.) as usize).min(signal_plot.signal().len());
+        let found = match (signal_plot.signal(), self.trace) {
+            (Signal::Real(sig), _) => {
+                find_edge(sig, |&x| x, self.level, self.edge, from, direction)
+            }
+            (Signal::Complex(_), Trace::Magnitude) => match signal_plot.magnitude() {
+                Some(Signal::Real(mag)) => {
+                    find_edge(mag, |&x| x, self.level, self.edge, from, direction)
+                }
+                _ => None,
+            },
+            (Signal::Complex(sig), Trace::Inphase) => {
+                find_edge(sig, |x| x.re, self.level, self.edge, from, direction)
+            }
+            (Signal::Complex(sig), Trace::Quadrature) => {
+                find_edge(sig, |x| x.im, self.level, self.edge, from, direction)
+            }
+        };
+        match found {
+            Some(found) => {
+                let half = bounds.width() / 2.;
+                signal_plot.set_x_range(found as f64 - half, found as f64 + half);
+                self.found = Some(found);
+                self.err_msg = None;
+            }
+            None => {
+                self.err_msg = Some("No matching edge found.".to_owned());
+            }
+        }
+    }
+}
@@ -79,6 +79,11 @@ impl<T: Downconvert<T> + Clone> MultiResolutionSeries<T> {
         &self.data[ratio.trailing_zeros() as usize][start..end]
     }
 
+    /// 最粗糙的一层, 每个元素对应 `max_ratio()` 个原始样本
+    pub fn coarsest(&self) -> &[T] {
+        self.data.last().unwrap()
+    }
+
     pub fn max_ratio(&self) -> usize {
         1 << (self.data.len() - 1)
     }
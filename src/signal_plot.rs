@@ -1,28 +1,262 @@
+use crate::annotation::Annotation;
+use crate::cursors::Cursors;
+use crate::persistence::PersistenceView;
 use crate::series::MultiResolutionSeries;
 use eframe::egui::{self, Color32, Key, Vec2b};
-use egui_plot::{Legend, Line, PlotBounds, PlotPoints};
+use egui_plot::{Legend, Line, PlotBounds, PlotMemory, PlotPoints, PlotUi, Points, VLine};
 use rustfft::num_complex::Complex;
+use std::sync::Arc;
 
 pub enum Signal {
     Real(MultiResolutionSeries<f32>),
     Complex(MultiResolutionSeries<Complex<f32>>),
 }
 
+impl Signal {
+    pub fn len(&self) -> usize {
+        match self {
+            Signal::Real(sig) => sig.len(),
+            Signal::Complex(sig) => sig.len(),
+        }
+    }
+}
+
+pub const INPHASE_COLOR: Color32 = Color32::from_rgb(80, 160, 255);
+pub const QUADRATURE_COLOR: Color32 = Color32::from_rgb(255, 160, 60);
+pub const MAGNITUDE_COLOR: Color32 = Color32::from_rgb(120, 220, 120);
+const PLAYHEAD_COLOR: Color32 = Color32::from_rgb(255, 80, 80);
+const SYMBOL_COLOR: Color32 = Color32::from_rgb(230, 230, 80);
+/// 派生曲线依次使用的颜色
+const DERIVED_COLORS: [Color32; 4] = [
+    Color32::from_rgb(255, 100, 160),
+    Color32::from_rgb(80, 220, 220),
+    Color32::from_rgb(230, 230, 90),
+    Color32::from_rgb(190, 140, 255),
+];
+
+/// 由原始信号计算得到的曲线, 例如下变频或者滤波的结果
+pub struct DerivedTrace {
+    pub name: String,
+    pub signal: Arc<Signal>,
+    /// 第一个样本在原始信号中的位置
+    pub offset: f64,
+    /// 相邻两个样本之间隔了多少个原始样本, 抽取后大于 1
+    pub step: f64,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum PlotStyle {
+    Line,
+    /// 采样保持, 每个样本保持到下一个样本
+    Step,
+    Stem,
+}
+
 pub struct SignalPlot {
-    signal: Option<Signal>,
-    signal_mag: Option<Signal>,
+    signal: Option<Arc<Signal>>,
+    signal_mag: Option<Arc<Signal>>,
     range: std::ops::Range<usize>,
     first_render: bool,
     reset_view: bool,
-    reset_to_last_view: bool,
     x_axis_time: bool,
     sample_rate: u32,
+    /// 采集时的中心频率, 单位 Hz
+    center_freq: f64,
     zoom_history: Vec<PlotBounds>,
+    /// 后退之后可以前进到的视图, 新的导航会清空它
+    forward_history: Vec<PlotBounds>,
+    next_bounds: Option<PlotBounds>,
     bounds: PlotBounds,
     magnitude_visible: bool,
+    plot_style: PlotStyle,
+    persistence: bool,
+    persistence_view: PersistenceView,
+    plot_size: egui::Vec2,
+    cursors: Cursors,
+    annotations: Vec<Annotation>,
     measure_active: bool,
     measure_x1: Option<f64>,
     measure_x2: Option<f64>,
+    derived: Vec<(DerivedTrace, Color32)>,
+    /// 正在播放的位置, 单位为样本
+    playhead: Option<f64>,
+    /// 数字解调的符号判决时刻 (按位置排序) 和符号取值, 取值归一化到 0 到 1
+    symbols: Vec<(f64, f32)>,
+}
+
+/// 绘制一条曲线. 原始分辨率 (ratio == 1) 下按 `style` 绘制, 并且在样本足够稀疏时标出每个样本点;
+/// 降采样时绘制 max/min 之间的填充带, 填充带的网格放入 `envelopes` 由调用者在绘图后绘制.
+/// `points` 的横坐标是曲线自身的样本序号, 经过 `x_scale` = [偏移, 步长] 换算成原始信号的样本位置
+#[allow(clippy::too_many_arguments)]
+fn draw_trace(
+    plot_ui: &mut PlotUi,
+    name: &str,
+    color: Color32,
+    mut points: Vec<[f64; 2]>,
+    ratio: usize,
+    x_scale: [f64; 2],
+    style: PlotStyle,
+    marker_samples: usize,
+    envelopes: &mut Vec<(String, egui::Mesh)>,
+) {
+    if ratio > 1 {
+        draw_envelope(plot_ui, name, color, &points, ratio, x_scale, envelopes);
+        return;
+    }
+    for p in points.iter_mut() {
+        p[0] = x_scale[0] + p[0] * x_scale[1];
+    }
+    let show_markers = points.len() <= marker_samples;
+    match style {
+        PlotStyle::Line => {
+            plot_ui.line(
+                Line::new(PlotPoints::new(points.clone()))
+                    .name(name)
+                    .color(color),
+            );
+        }
+        PlotStyle::Step => {
+            let mut steps = Vec::with_capacity(points.len() * 2);
+            for (i, p) in points.iter().enumerate() {
+                steps.push(*p);
+                if let Some(next) = points.get(i + 1) {
+                    steps.push([next[0], p[1]]);
+                }
+            }
+            plot_ui.line(Line::new(PlotPoints::new(steps)).name(name).color(color));
+        }
+        PlotStyle::Stem => {
+            plot_ui.points(
+                Points::new(PlotPoints::new(points))
+                    .stems(0.)
+                    .radius(if show_markers { 2.5 } else { 0. })
+                    .name(name)
+                    .color(color),
+            );
+            return;
+        }
+    }
+    if show_markers {
+        plot_ui.points(
+            Points::new(PlotPoints::new(points))
+                .radius(2.5)
+                .name(name)
+                .color(color),
+        );
+    }
+}
+
+/// 降采样后的数据是交替的 (max, min), 每一对覆盖 `2 * ratio` 个原始样本
+fn draw_envelope(
+    plot_ui: &mut PlotUi,
+    name: &str,
+    color: Color32,
+    points: &[[f64; 2]],
+    ratio: usize,
+    x_scale: [f64; 2],
+    envelopes: &mut Vec<(String, egui::Mesh)>,
+) {
+    if points.is_empty() {
+        return;
+    }
+    // 保证从一对 (max, min) 的开头开始
+    let offset = (points[0][0] as usize / ratio) & 1;
+    let mut upper = Vec::with_capacity(points.len() / 2);
+    let mut lower = Vec::with_capacity(points.len() / 2);
+    for pair in points[offset..].chunks_exact(2) {
+        let x = x_scale[0] + (pair[0][0] + ratio as f64) * x_scale[1];
+        upper.push([x, pair[0][1]]);
+        lower.push([x, pair[1][1]]);
+    }
+    let fill = color.gamma_multiply(0.4);
+    let mut mesh = egui::Mesh::default();
+    for (i, (top, bottom)) in upper.iter().zip(lower.iter()).enumerate() {
+        let top = plot_ui.screen_from_plot(egui_plot::PlotPoint::new(top[0], top[1]));
+        let bottom = plot_ui.screen_from_plot(egui_plot::PlotPoint::new(bottom[0], bottom[1]));
+        mesh.colored_vertex(top, fill);
+        // 保证每一列至少有一个像素高
+        mesh.colored_vertex(egui::pos2(bottom.x, bottom.y.max(top.y + 1.)), fill);
+        if i > 0 {
+            let i = i as u32 * 2;
+            mesh.add_triangle(i - 2, i - 1, i);
+            mesh.add_triangle(i - 1, i + 1, i);
+        }
+    }
+    envelopes.push((name.to_owned(), mesh));
+    plot_ui.line(Line::new(PlotPoints::new(upper)).name(name).color(color));
+    plot_ui.line(Line::new(PlotPoints::new(lower)).name(name).color(color));
+}
+
+/// 只取派生曲线在 `x_range` 内的部分, 复数曲线分别画出实部和虚部
+#[allow(clippy::too_many_arguments)]
+fn draw_derived(
+    plot_ui: &mut PlotUi,
+    trace: &DerivedTrace,
+    color: Color32,
+    x_range: [f64; 2],
+    max_samples: usize,
+    style: PlotStyle,
+    marker_samples: usize,
+    envelopes: &mut Vec<(String, egui::Mesh)>,
+) {
+    let len = trace.signal.len();
+    let start = ((x_range[0] - trace.offset) / trace.step).floor().max(0.) as usize;
+    let end = (((x_range[1] - trace.offset) / trace.step).ceil().max(0.) as usize + 1).min(len);
+    if end <= start {
+        return;
+    }
+    let x_scale = [trace.offset, trace.step];
+    match trace.signal.as_ref() {
+        Signal::Real(signal) => {
+            let ratio = auto_ratio(max_samples, signal.max_ratio(), end - start);
+            let points = signal
+                .get(start..end, ratio)
+                .iter()
+                .enumerate()
+                .map(|(i, &y)| [(start + i * ratio) as f64, y as f64])
+                .collect();
+            draw_trace(
+                plot_ui,
+                &trace.name,
+                color,
+                points,
+                ratio,
+                x_scale,
+                style,
+                marker_samples,
+                envelopes,
+            );
+        }
+        Signal::Complex(signal) => {
+            let ratio = auto_ratio(max_samples, signal.max_ratio(), end - start);
+            let data = signal.get(start..end, ratio);
+            let parts = [
+                (format!("{} I", trace.name), color, false),
+                (format!("{} Q", trace.name), color.gamma_multiply(0.6), true),
+            ];
+            for (name, color, imag) in parts {
+                let points = data
+                    .iter()
+                    .enumerate()
+                    .map(|(i, y)| {
+                        let y = if imag { y.im } else { y.re };
+                        [(start + i * ratio) as f64, y as f64]
+                    })
+                    .collect();
+                draw_trace(
+                    plot_ui,
+                    &name,
+                    color,
+                    points,
+                    ratio,
+                    x_scale,
+                    style,
+                    marker_samples,
+                    envelopes,
+                );
+            }
+        }
+    }
 }
 
 fn auto_ratio(max_points: usize, max_ratio: usize, nsamples: usize) -> usize {
@@ -44,15 +278,26 @@ impl SignalPlot {
             range: 0..0,
             first_render: true,
             reset_view: false,
-            reset_to_last_view: false,
             x_axis_time: true,
             sample_rate: 1,
+            center_freq: 0.,
             zoom_history: Vec::new(),
+            forward_history: Vec::new(),
+            next_bounds: None,
             bounds: PlotBounds::from_min_max([0., 0.], [0., 0.]),
             magnitude_visible: false,
+            plot_style: PlotStyle::Line,
+            persistence: false,
+            persistence_view: PersistenceView::new(),
+            plot_size: egui::Vec2::ZERO,
+            cursors: Cursors::new(),
+            annotations: Vec::new(),
             measure_active: false,
             measure_x1: None,
             measure_x2: None,
+            derived: Vec::new(),
+            playhead: None,
+            symbols: Vec::new(),
         }
     }
 
@@ -70,6 +315,10 @@ impl SignalPlot {
             space_pressed = true;
         }
         let max_samples = (ui.available_width() * 2.5) as usize;
+        let marker_samples = (ui.available_width() / 4.) as usize;
+        let style = self.plot_style;
+        let plot_id = ui.make_persistent_id(egui::Id::new("signal"));
+        let mut envelopes = Vec::new();
         let x_label = if self.x_axis_time {
             "Time (s)"
         } else {
@@ -79,13 +328,14 @@ impl SignalPlot {
         let x_axis_time = self.x_axis_time;
         let mut measure_x1_pos = egui::pos2(0., 0.);
         let mut measure_x2_pos = egui::pos2(0., 0.);
+        let cursor_grabbed = self.cursors.grabbed();
         let response = egui_plot::Plot::new("signal")
             .legend(Legend::default())
             .auto_bounds(Vec2b::new(false, false))
             .allow_double_click_reset(false)
             .allow_zoom(Vec2b::new(!z_pressed, z_pressed))
-            .allow_drag(space_pressed && !r_pressed)
-            .allow_boxed_zoom(!space_pressed && !r_pressed)
+            .allow_drag(space_pressed && !r_pressed && !cursor_grabbed)
+            .allow_boxed_zoom(!space_pressed && !r_pressed && !cursor_grabbed)
             .boxed_zoom_pointer_button(egui::PointerButton::Primary)
             .x_axis_formatter(move |mark, _range| {
                 if x_axis_time {
@@ -118,7 +368,7 @@ impl SignalPlot {
                 bounds = plot_ui.plot_bounds();
 
                 if self.reset_view {
-                    if let Some(sig) = self.signal.as_ref() {
+                    if let Some(sig) = self.signal.as_deref() {
                         match sig {
                             Signal::Real(sig) => {
                                 plot_ui.set_plot_bounds(PlotBounds::from_min_max(
@@ -137,11 +387,8 @@ impl SignalPlot {
                         plot_ui.set_plot_bounds(PlotBounds::from_min_max([0., -0.99], [1000., 1.]));
                     }
                     self.reset_view = false;
-                } else if self.reset_to_last_view {
-                    if let Some(bounds) = self.zoom_history.pop() {
-                        plot_ui.set_plot_bounds(bounds);
-                    }
-                    self.reset_to_last_view = false;
+                } else if let Some(bounds) = self.next_bounds.take() {
+                    plot_ui.set_plot_bounds(bounds);
                 }
                 if plot_ui.response().clicked_by(egui::PointerButton::Primary) && r_pressed {
                     self.measure_active = false;
@@ -158,8 +405,9 @@ impl SignalPlot {
                 if plot_ui
                     .response()
                     .drag_started_by(egui::PointerButton::Primary)
+                    && !cursor_grabbed
                 {
-                    self.zoom_history.push(bounds.clone());
+                    self.push_history(bounds);
                     if r_pressed {
                         self.measure_active = true;
                         self.measure_x1 = plot_ui.pointer_coordinate().map(|p| p.x);
@@ -178,14 +426,48 @@ impl SignalPlot {
                     measure_x2_pos = plot_ui.screen_from_plot(egui_plot::PlotPoint::new(x_max, 0.));
                 }
                 bounds = plot_ui.plot_bounds();
+                self.cursors.update(plot_ui, &bounds);
+                self.cursors.draw(plot_ui);
+                if let Some(x) = self.playhead {
+                    plot_ui.vline(VLine::new(x).color(PLAYHEAD_COLOR).width(1.5));
+                }
                 self.bounds = bounds.clone();
                 if self.signal.is_none() {
                     return;
                 }
-                let signal = self.signal.as_ref().unwrap();
+                let signal = self.signal.as_deref().unwrap();
 
                 let x1 = *bounds.range_x().start();
                 let x2 = *bounds.range_x().end();
+                for (trace, color) in self.derived.iter() {
+                    draw_derived(
+                        plot_ui,
+                        trace,
+                        *color,
+                        [x1, x2],
+                        max_samples,
+                        style,
+                        marker_samples,
+                        &mut envelopes,
+                    );
+                }
+                // 每个像素少于一个样本时荧光显示没有意义, 退回到曲线显示
+                if self.persistence && x2 - x1 > self.plot_size.x as f64 {
+                    self.persistence_view.update(
+                        plot_ui.ctx(),
+                        self.signal.as_ref().unwrap(),
+                        self.signal_mag.as_ref(),
+                        bounds,
+                        self.plot_size,
+                        self.magnitude_visible,
+                    );
+                    if self.persistence_view.is_ready() {
+                        self.range = (x1.floor().max(0.) as usize)
+                            ..(x2.ceil().min(signal.len() as f64) as usize + 1);
+                        self.persistence_view.show(plot_ui);
+                        return;
+                    }
+                }
                 match signal {
                     Signal::Real(signal) => {
                         let index_start = x1.floor().max(0.) as usize;
@@ -197,13 +479,22 @@ impl SignalPlot {
                         let ratio =
                             auto_ratio(max_samples, signal.max_ratio(), index_end - index_start);
                         let data = signal.get(index_start..index_end, ratio);
-                        let re = PlotPoints::new(
-                            data.iter()
-                                .enumerate()
-                                .map(|(i, &y)| [(index_start + i * ratio) as f64, y as f64])
-                                .collect(),
+                        let re = data
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &y)| [(index_start + i * ratio) as f64, y as f64])
+                            .collect();
+                        draw_trace(
+                            plot_ui,
+                            "inphase",
+                            INPHASE_COLOR,
+                            re,
+                            ratio,
+                            [0., 1.],
+                            style,
+                            marker_samples,
+                            &mut envelopes,
                         );
-                        plot_ui.line(Line::new(re).name("inphase"));
                     }
                     Signal::Complex(signal) => {
                         let index_start = x1.floor().max(0.) as usize;
@@ -215,37 +506,112 @@ impl SignalPlot {
                         let ratio =
                             auto_ratio(max_samples, signal.max_ratio(), index_end - index_start);
                         if self.magnitude_visible {
-                            if let Signal::Real(signal_mag) = self.signal_mag.as_ref().unwrap() {
+                            if let Signal::Real(signal_mag) = self.signal_mag.as_deref().unwrap() {
                                 let data = signal_mag.get(index_start..index_end, ratio);
-                                let mag = PlotPoints::new(
-                                    data.iter()
-                                        .enumerate()
-                                        .map(|(i, &y)| [(index_start + i * ratio) as f64, y as f64])
-                                        .collect(),
+                                let mag = data
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, &y)| [(index_start + i * ratio) as f64, y as f64])
+                                    .collect();
+                                draw_trace(
+                                    plot_ui,
+                                    "magnitude",
+                                    MAGNITUDE_COLOR,
+                                    mag,
+                                    ratio,
+                                    [0., 1.],
+                                    style,
+                                    marker_samples,
+                                    &mut envelopes,
                                 );
-                                plot_ui.line(Line::new(mag).name("magnitude"));
                             }
                         } else {
                             let data = signal.get(index_start..index_end, ratio);
-                            let re = PlotPoints::new(
-                                data.iter()
-                                    .enumerate()
-                                    .map(|(i, y)| [(index_start + i * ratio) as f64, y.re as f64])
-                                    .collect(),
+                            let re = data
+                                .iter()
+                                .enumerate()
+                                .map(|(i, y)| [(index_start + i * ratio) as f64, y.re as f64])
+                                .collect();
+                            let im = data
+                                .iter()
+                                .enumerate()
+                                .map(|(i, y)| [(index_start + i * ratio) as f64, y.im as f64])
+                                .collect();
+                            draw_trace(
+                                plot_ui,
+                                "inphase",
+                                INPHASE_COLOR,
+                                re,
+                                ratio,
+                                [0., 1.],
+                                style,
+                                marker_samples,
+                                &mut envelopes,
                             );
-                            let im = PlotPoints::new(
-                                data.iter()
-                                    .enumerate()
-                                    .map(|(i, y)| [(index_start + i * ratio) as f64, y.im as f64])
-                                    .collect(),
+                            draw_trace(
+                                plot_ui,
+                                "quadrature",
+                                QUADRATURE_COLOR,
+                                im,
+                                ratio,
+                                [0., 1.],
+                                style,
+                                marker_samples,
+                                &mut envelopes,
                             );
-                            plot_ui.line(Line::new(re).name("inphase"));
-                            plot_ui.line(Line::new(im).name("quadrature"));
                         }
                     }
                 }
             });
         let rect = response.response.rect;
+        self.plot_size = rect.size();
+        let painter = ui.painter_at(rect);
+        for annotation in self.annotations.iter() {
+            let x1 = response
+                .transform
+                .position_from_point_x(annotation.start as f64);
+            let x2 = response
+                .transform
+                .position_from_point_x(annotation.end as f64);
+            if x2 < rect.left() || x1 > rect.right() {
+                continue;
+            }
+            let region = egui::Rect::from_x_y_ranges(egui::Rangef::new(x1, x2), rect.y_range());
+            painter.rect_filled(region, 0., annotation.color.gamma_multiply(0.15));
+            painter.vline(x1, rect.y_range(), egui::Stroke::new(1., annotation.color));
+            painter.vline(x2, rect.y_range(), egui::Stroke::new(1., annotation.color));
+            painter.text(
+                egui::pos2(x1.max(rect.left()) + 4., rect.top() + 4.),
+                egui::Align2::LEFT_TOP,
+                &annotation.name,
+                egui::FontId::proportional(13.),
+                annotation.color,
+            );
+        }
+        // 符号标记画在底部, 刻度的高度表示符号取值. 太密时看不清, 不画
+        let left = response.transform.value_from_position(rect.left_top()).x;
+        let right = response.transform.value_from_position(rect.right_top()).x;
+        let first = self.symbols.partition_point(|&(x, _)| x < left);
+        let last = self.symbols.partition_point(|&(x, _)| x <= right);
+        if last - first <= (rect.width() / 4.) as usize {
+            for &(x, level) in self.symbols[first..last].iter() {
+                let x = response.transform.position_from_point_x(x);
+                let top = rect.bottom() - 6. - 18. * level;
+                painter.vline(
+                    x,
+                    egui::Rangef::new(top, rect.bottom()),
+                    egui::Stroke::new(1.5, SYMBOL_COLOR),
+                );
+            }
+        }
+        let hidden_items = PlotMemory::load(ui.ctx(), plot_id)
+            .map(|memory| memory.hidden_items)
+            .unwrap_or_default();
+        for (name, mesh) in envelopes {
+            if !hidden_items.contains(&name) {
+                painter.add(mesh);
+            }
+        }
         if self.measure_x1.is_some() && self.measure_x2.is_some() {
             ui.painter().vline(
                 measure_x1_pos.x,
@@ -270,12 +636,90 @@ impl SignalPlot {
         }
     }
 
+    pub fn show_overview(&mut self, ui: &mut egui::Ui) {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), 40.),
+            egui::Sense::click_and_drag(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0., Color32::from_gray(20));
+        let (values, ratio) = match (self.signal.as_deref(), self.signal_mag.as_deref()) {
+            (_, Some(Signal::Real(sig))) | (Some(Signal::Real(sig)), _) => {
+                (sig.coarsest().to_vec(), sig.max_ratio())
+            }
+            (Some(Signal::Complex(sig)), _) => (
+                sig.coarsest().iter().map(|s| s.re).collect(),
+                sig.max_ratio(),
+            ),
+            _ => return,
+        };
+        let len = self.signal().len() as f32;
+        let y_min = values.iter().fold(f32::INFINITY, |a, &b| a.min(b));
+        let y_max = values.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        let y_span = if y_max > y_min { y_max - y_min } else { 1. };
+        let to_screen_y = |y: f32| rect.bottom() - 2. - (y - y_min) / y_span * (rect.height() - 4.);
+        let stroke = egui::Stroke::new(1., Color32::from_rgb(100, 150, 250));
+        let columns = rect.width() as usize;
+        for col in 0..columns {
+            let start = (len * col as f32 / columns as f32) as usize / ratio;
+            let end = ((len * (col + 1) as f32 / columns as f32) as usize / ratio)
+                .max(start + 1)
+                .min(values.len());
+            if start >= end {
+                continue;
+            }
+            let (min, max) = values[start..end]
+                .iter()
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &y| {
+                    (min.min(y), max.max(y))
+                });
+            let x = rect.left() + col as f32 + 0.5;
+            painter.vline(
+                x,
+                egui::Rangef::new(to_screen_y(max), to_screen_y(min)),
+                stroke,
+            );
+        }
+
+        // 高亮当前可见窗口
+        let x1 = *self.bounds.range_x().start() as f32;
+        let x2 = *self.bounds.range_x().end() as f32;
+        let window = egui::Rect::from_x_y_ranges(
+            egui::Rangef::new(
+                rect.left() + x1.max(0.) / len * rect.width(),
+                rect.left() + x2.min(len) / len * rect.width(),
+            ),
+            rect.y_range(),
+        );
+        painter.rect_filled(window, 0., Color32::from_white_alpha(30));
+        painter.rect_stroke(window, 0., egui::Stroke::new(1., Color32::WHITE));
+
+        if response.drag_started() || response.clicked() {
+            self.push_history(self.bounds);
+        }
+        if response.dragged() || response.clicked() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let center = ((pos.x - rect.left()) / rect.width() * len) as f64;
+                let half = self.bounds.width() / 2.;
+                self.next_bounds = Some(PlotBounds::from_min_max(
+                    [center - half, *self.bounds.range_y().start()],
+                    [center + half, *self.bounds.range_y().end()],
+                ));
+            }
+        }
+    }
+
     pub fn set_signal(&mut self, signal: Signal, signal_mag: Option<Signal>) {
         self.measure_x1 = None;
         self.measure_x2 = None;
         self.zoom_history.clear();
-        self.signal = Some(signal);
-        self.signal_mag = signal_mag;
+        self.forward_history.clear();
+        self.persistence_view.clear();
+        self.annotations.clear();
+        self.derived.clear();
+        self.symbols.clear();
+        self.signal = Some(Arc::new(signal));
+        self.signal_mag = signal_mag.map(Arc::new);
     }
 
     pub fn have_signal(&self) -> bool {
@@ -283,25 +727,119 @@ impl SignalPlot {
     }
 
     pub fn signal(&self) -> &Signal {
-        self.signal.as_ref().unwrap()
+        self.signal.as_deref().unwrap()
+    }
+
+    pub fn magnitude(&self) -> Option<&Signal> {
+        self.signal_mag.as_deref()
+    }
+
+    pub fn shared_magnitude(&self) -> Option<Arc<Signal>> {
+        self.signal_mag.clone()
+    }
+
+    pub fn shared_signal(&self) -> Option<Arc<Signal>> {
+        self.signal.clone()
     }
 
     pub fn range(&self) -> std::ops::Range<usize> {
         self.range.clone()
     }
 
+    /// 返回测量区间对应的样本范围, 没有测量区间时返回当前可见范围
+    pub fn selected_range(&self) -> std::ops::Range<usize> {
+        self.measure_range().unwrap_or_else(|| self.range())
+    }
+
+    /// 测量区间 (R + 拖动) 对应的样本范围
+    pub fn measure_range(&self) -> Option<std::ops::Range<usize>> {
+        if let (Some(x1), Some(x2), Some(sig)) =
+            (self.measure_x1, self.measure_x2, self.signal.as_deref())
+        {
+            let end = (x1.max(x2).floor().max(0.) as usize + 1).min(sig.len());
+            let start = (x1.min(x2).ceil().max(0.) as usize).min(end);
+            return Some(start..end);
+        }
+        None
+    }
+
+    /// 跳转到指定的 x 范围, y 范围保持不变
+    pub fn set_x_range(&mut self, x_min: f64, x_max: f64) {
+        self.set_bounds(PlotBounds::from_min_max(
+            [x_min, *self.bounds.range_y().start()],
+            [x_max, *self.bounds.range_y().end()],
+        ));
+    }
+
+    pub fn bounds(&self) -> PlotBounds {
+        self.bounds
+    }
+
+    pub fn set_bounds(&mut self, bounds: PlotBounds) {
+        self.push_history(self.bounds);
+        self.next_bounds = Some(bounds);
+    }
+
+    fn push_history(&mut self, bounds: PlotBounds) {
+        self.zoom_history.push(bounds);
+        self.forward_history.clear();
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    pub fn annotations_mut(&mut self) -> &mut Vec<Annotation> {
+        &mut self.annotations
+    }
+
     pub fn reset_view(&mut self) {
         self.reset_view = true;
     }
 
-    pub fn return_last_view(&mut self) {
-        self.reset_to_last_view = true;
+    pub fn go_back(&mut self) {
+        if let Some(bounds) = self.zoom_history.pop() {
+            self.forward_history.push(self.bounds);
+            self.next_bounds = Some(bounds);
+        }
+    }
+
+    pub fn go_forward(&mut self) {
+        if let Some(bounds) = self.forward_history.pop() {
+            self.zoom_history.push(self.bounds);
+            self.next_bounds = Some(bounds);
+        }
+    }
+
+    pub fn add_derived(&mut self, trace: DerivedTrace) {
+        let color = DERIVED_COLORS[self.derived.len() % DERIVED_COLORS.len()];
+        self.derived.push((trace, color));
+    }
+
+    pub fn derived(&self) -> impl Iterator<Item = &DerivedTrace> {
+        self.derived.iter().map(|(trace, _)| trace)
+    }
+
+    pub fn set_playhead(&mut self, playhead: Option<f64>) {
+        self.playhead = playhead;
+    }
+
+    pub fn set_symbols(&mut self, symbols: Vec<(f64, f32)>) {
+        self.symbols = symbols;
+    }
+
+    pub fn clear_derived(&mut self) {
+        self.derived.clear();
     }
 
     pub fn set_sample_rate(&mut self, sample_rate: u32) {
         self.sample_rate = sample_rate;
     }
 
+    pub fn set_center_freq(&mut self, center_freq: f64) {
+        self.center_freq = center_freq;
+    }
+
     pub fn window_time(&self) -> f64 {
         let x1;
         let x2;
@@ -352,10 +890,59 @@ impl SignalPlot {
         index_end + 1 - index_start
     }
 
+    pub fn cursors(&self) -> &Cursors {
+        &self.cursors
+    }
+
+    pub fn set_cursors_visible(&mut self, visible: bool) {
+        if visible && !self.cursors.visible {
+            self.cursors.reset();
+        }
+        self.cursors.visible = visible;
+    }
+
+    /// 每条曲线在位置 `x` 处 (四舍五入到最近的样本) 的原始样本值
+    pub fn trace_values(&self, x: f64) -> Vec<(&'static str, f32)> {
+        let Some(signal) = self.signal.as_deref() else {
+            return Vec::new();
+        };
+        if x < -0.5 || x.round() as usize >= signal.len() {
+            return Vec::new();
+        }
+        let i = x.round() as usize;
+        match signal {
+            Signal::Real(sig) => vec![("inphase", sig.get(i..i + 1, 1)[0])],
+            Signal::Complex(sig) => {
+                let s = sig.get(i..i + 1, 1)[0];
+                vec![
+                    ("inphase", s.re),
+                    ("quadrature", s.im),
+                    ("magnitude", s.norm()),
+                ]
+            }
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn center_freq(&self) -> f64 {
+        self.center_freq
+    }
+
+    pub fn toggle_persistence(&mut self) {
+        self.persistence = !self.persistence;
+    }
+
     pub fn toggle_magnitude(&mut self) {
         self.magnitude_visible = !self.magnitude_visible;
     }
 
+    pub fn set_plot_style(&mut self, style: PlotStyle) {
+        self.plot_style = style;
+    }
+
     pub fn measure_frequency(&self) -> Option<f64> {
         if self.measure_x1.is_some() && self.measure_x2.is_some() {
             return Some(1. / self.window_time());
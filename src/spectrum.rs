@@ -0,0 +1,211 @@
+use eframe::egui::{Align2, Color32, CursorIcon, PointerButton};
+use egui_plot::{MarkerShape, PlotPoint, PlotPoints, PlotUi, Points, Text, VLine};
+
+const MARKER_COLOR: Color32 = Color32::from_rgb(255, 220, 80);
+/// 鼠标距离标记多少像素以内可以拖动
+const GRAB_DISTANCE: f32 = 5.;
+
+/// 找出高于 `threshold` 的局部最大值, 按电平从高到低最多返回 `count` 个下标
+pub fn find_peaks(psd: &[f64], threshold: f64, count: usize) -> Vec<usize> {
+    let mut peaks: Vec<usize> = (0..psd.len())
+        .filter(|&i| {
+            psd[i] >= threshold
+                && (i == 0 || psd[i] > psd[i - 1])
+                && (i + 1 == psd.len() || psd[i] >= psd[i + 1])
+        })
+        .collect();
+    peaks.sort_by(|&a, &b| psd[b].total_cmp(&psd[a]));
+    peaks.truncate(count);
+    peaks
+}
+
+/// 取离 `freq` 最近的频点上的电平, `freqs` 需要是升序的
+pub fn level_at(freqs: &[f64], psd: &[f64], freq: f64) -> Option<(f64, f64)> {
+    if freqs.is_empty() {
+        return None;
+    }
+    let i = freqs.partition_point(|&f| f < freq).min(freqs.len() - 1);
+    let i = if i > 0 && (freq - freqs[i - 1]).abs() < (freqs[i] - freq).abs() {
+        i - 1
+    } else {
+        i
+    };
+    Some((freqs[i], psd[i]))
+}
+
+/// 占用带宽包含的功率比例
+const OCCUPIED_FRACTION: f64 = 0.99;
+
+/// 信道测量结果, 功率为 dB, 频率为 Hz
+pub struct ChannelMeasurement {
+    pub power: f64,
+    pub occupied_bandwidth: f64,
+    /// 相邻信道和主信道的功率比, 单位 dBc, 超出频谱范围时为 `None`
+    pub acpr_lower: Option<f64>,
+    pub acpr_upper: Option<f64>,
+    /// 整个频谱的中位数作为噪声底, 单位和 `psd` 相同
+    pub noise_floor: f64,
+    pub snr: f64,
+}
+
+/// 在 `[span[0], span[1]]` 内积分功率. `psd` 为 dB, `bin_scale` 把每个频点的线性值换算成功率:
+/// 功率谱密度时为频点间隔, 功率谱时为等效噪声带宽的倒数
+pub fn measure_channel(
+    freqs: &[f64],
+    psd: &[f64],
+    span: [f64; 2],
+    bin_scale: f64,
+) -> Option<ChannelMeasurement> {
+    let (low, high) = (span[0].min(span[1]), span[0].max(span[1]));
+    let width = high - low;
+    let linear: Vec<f64> = psd.iter().map(|&x| 10f64.powf(x / 10.)).collect();
+    let channel_bins = |low: f64, high: f64| {
+        freqs.partition_point(|&f| f < low)..freqs.partition_point(|&f| f <= high)
+    };
+    let channel_power =
+        |range: std::ops::Range<usize>| linear[range].iter().sum::<f64>() * bin_scale;
+    let bins = channel_bins(low, high);
+    if bins.is_empty() {
+        return None;
+    }
+    let power = channel_power(bins.clone());
+
+    // 从两边各去掉 (1 - 99%) / 2 的功率
+    let edge = power * (1. - OCCUPIED_FRACTION) / 2.;
+    let mut sum = 0.;
+    let mut lower = bins.start;
+    for i in bins.clone() {
+        sum += linear[i] * bin_scale;
+        if sum > edge {
+            lower = i;
+            break;
+        }
+    }
+    sum = 0.;
+    let mut upper = bins.end - 1;
+    for i in bins.clone().rev() {
+        sum += linear[i] * bin_scale;
+        if sum > edge {
+            upper = i;
+            break;
+        }
+    }
+    let occupied_bandwidth = (freqs[upper] - freqs[lower]).max(0.);
+
+    let first = *freqs.first()?;
+    let last = *freqs.last()?;
+    let adjacent = |low: f64, high: f64| {
+        if low < first || high > last {
+            return None;
+        }
+        let bins = channel_bins(low, high);
+        (!bins.is_empty()).then(|| 10. * (channel_power(bins) / power).log10())
+    };
+
+    let mut sorted = linear.clone();
+    let mid = sorted.len() / 2;
+    let (_, noise_floor, _) = sorted.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+    let noise_power = *noise_floor * bins.len() as f64 * bin_scale;
+    let snr = 10. * ((power - noise_power).max(f64::MIN_POSITIVE) / noise_power).log10();
+
+    Some(ChannelMeasurement {
+        power: 10. * power.log10(),
+        occupied_bandwidth,
+        acpr_lower: adjacent(low - width, low),
+        acpr_upper: adjacent(high, high + width),
+        noise_floor: 10. * noise_floor.log10(),
+        snr,
+    })
+}
+
+/// 频谱上的频率标记, 第一个标记作为差值读数的参考
+pub struct SpectrumMarkers {
+    /// 标记所在的基带频率, 单位 Hz
+    pub freqs: Vec<f64>,
+    hovered: Option<usize>,
+    dragging: Option<usize>,
+}
+
+impl SpectrumMarkers {
+    pub fn new() -> Self {
+        Self {
+            freqs: Vec::new(),
+            hovered: None,
+            dragging: None,
+        }
+    }
+
+    /// 鼠标位于某个标记上或者正在拖动标记, 此时绘图区不应该响应拖动
+    pub fn grabbed(&self) -> bool {
+        self.hovered.is_some() || self.dragging.is_some()
+    }
+
+    /// 处理拖动, 双击空白处添加标记. `offset` 是频率轴上显示的偏移
+    pub fn update(&mut self, plot_ui: &mut PlotUi, offset: f64) {
+        let response = plot_ui.response().clone();
+        self.hovered = None;
+        if let Some(pointer) = response.hover_pos() {
+            let mut best = GRAB_DISTANCE;
+            for (i, &freq) in self.freqs.iter().enumerate() {
+                let pos = plot_ui.screen_from_plot(PlotPoint::new(freq + offset, 0.));
+                if (pos.x - pointer.x).abs() < best {
+                    best = (pos.x - pointer.x).abs();
+                    self.hovered = Some(i);
+                }
+            }
+        }
+        if response.drag_started_by(PointerButton::Primary) {
+            self.dragging = self.hovered;
+        }
+        if response.drag_stopped_by(PointerButton::Primary) {
+            self.dragging = None;
+        }
+        if let Some(pointer) = plot_ui.pointer_coordinate() {
+            if let Some(i) = self.dragging {
+                self.freqs[i] = pointer.x - offset;
+            } else if response.double_clicked() {
+                self.freqs.push(pointer.x - offset);
+            }
+        }
+        if self.dragging.or(self.hovered).is_some() {
+            plot_ui.ctx().set_cursor_icon(CursorIcon::ResizeHorizontal);
+        }
+    }
+
+    pub fn draw(&self, plot_ui: &mut PlotUi, freqs: &[f64], psd: &[f64], offset: f64) {
+        for (i, &freq) in self.freqs.iter().enumerate() {
+            let Some((freq, level)) = level_at(freqs, psd, freq) else {
+                return;
+            };
+            plot_ui.vline(
+                VLine::new(freq + offset)
+                    .color(MARKER_COLOR)
+                    .style(egui_plot::LineStyle::dashed_loose()),
+            );
+            plot_ui.points(
+                Points::new(PlotPoints::new(vec![[freq + offset, level]]))
+                    .shape(MarkerShape::Down)
+                    .filled(true)
+                    .radius(5.)
+                    .color(MARKER_COLOR),
+            );
+            plot_ui.text(
+                Text::new(PlotPoint::new(freq + offset, level), format!("M{}", i + 1))
+                    .anchor(Align2::CENTER_BOTTOM)
+                    .color(MARKER_COLOR),
+            );
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.freqs.remove(index);
+        self.hovered = None;
+        self.dragging = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.freqs.clear();
+        self.hovered = None;
+        self.dragging = None;
+    }
+}
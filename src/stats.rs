@@ -0,0 +1,53 @@
+use rayon::prelude::*;
+
+#[derive(Clone, Copy)]
+pub struct Stats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub rms: f64,
+}
+
+impl Stats {
+    pub fn peak_to_peak(&self) -> f64 {
+        self.max - self.min
+    }
+
+    /// 平均功率, 以满幅 1.0 为参考
+    pub fn power_dbfs(&self) -> f64 {
+        20. * self.rms.log10()
+    }
+
+    pub fn crest_factor(&self) -> f64 {
+        self.min.abs().max(self.max.abs()) / self.rms
+    }
+
+    pub fn dc_offset(&self) -> f64 {
+        self.mean
+    }
+}
+
+pub fn compute_stats<T: Sync>(data: &[T], value: impl Fn(&T) -> f32 + Sync) -> Stats {
+    let (min, max, sum, sum_sqr) = data
+        .par_chunks(65536)
+        .map(|chunk| {
+            chunk.iter().fold(
+                (f64::INFINITY, f64::NEG_INFINITY, 0., 0.),
+                |(min, max, sum, sum_sqr), x| {
+                    let x = value(x) as f64;
+                    (min.min(x), max.max(x), sum + x, sum_sqr + x * x)
+                },
+            )
+        })
+        .reduce(
+            || (f64::INFINITY, f64::NEG_INFINITY, 0., 0.),
+            |a, b| (a.0.min(b.0), a.1.max(b.1), a.2 + b.2, a.3 + b.3),
+        );
+    let count = data.len();
+    Stats {
+        min,
+        max,
+        mean: sum / count as f64,
+        rms: (sum_sqr / count as f64).sqrt(),
+    }
+}
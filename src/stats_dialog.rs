@@ -0,0 +1,119 @@
+use crate::signal_plot::{Signal, SignalPlot};
+use crate::stats::{compute_stats, Stats};
+use eframe::egui::{self, Grid};
+use std::sync::Arc;
+use std::thread;
+
+type StatsKey = (usize, std::ops::Range<usize>);
+type StatsTask = thread::JoinHandle<Vec<(String, Stats)>>;
+type StatsRow = (&'static str, fn(&Stats) -> f64);
+
+pub struct StatsDialog {
+    /// 统计结果对应的信号 (以指针区分) 和样本范围
+    key: StatsKey,
+    stats: Vec<(String, Stats)>,
+    task: Option<(StatsKey, StatsTask)>,
+}
+
+impl Default for StatsDialog {
+    fn default() -> Self {
+        Self {
+            key: (0, 0..0),
+            stats: Vec::new(),
+            task: None,
+        }
+    }
+}
+
+impl StatsDialog {
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool, signal_plot: &SignalPlot) {
+        if !*open {
+            return;
+        }
+        self.update(ctx, signal_plot);
+        egui::Window::new("Statistics")
+            .open(open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Samples {}..{}", self.key.1.start, self.key.1.end));
+                    if self.task.is_some() {
+                        ui.spinner();
+                    }
+                });
+                Grid::new("stats")
+                    .num_columns(self.stats.len() + 1)
+                    .striped(true)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("");
+                        for (name, _) in self.stats.iter() {
+                            ui.strong(name);
+                        }
+                        ui.end_row();
+                        let rows: [StatsRow; 8] = [
+                            ("Min", |s| s.min),
+                            ("Max", |s| s.max),
+                            ("Mean", |s| s.mean),
+                            ("RMS", |s| s.rms),
+                            ("Peak-to-peak", |s| s.peak_to_peak()),
+                            ("Power (dBFS)", |s| s.power_dbfs()),
+                            ("Crest factor", |s| s.crest_factor()),
+                            ("DC offset", |s| s.dc_offset()),
+                        ];
+                        for (label, value) in rows {
+                            ui.label(label);
+                            for (_, stats) in self.stats.iter() {
+                                ui.label(format!("{:.6}", value(stats)));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    /// 换信号时丢弃旧的结果和进行中的统计
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn update(&mut self, ctx: &egui::Context, signal_plot: &SignalPlot) {
+        if let Some((key, task)) = self.task.take() {
+            if !task.is_finished() {
+                self.task = Some((key, task));
+                ctx.request_repaint();
+                return;
+            }
+            if let Ok(stats) = task.join() {
+                self.key = key;
+                self.stats = stats;
+            }
+        }
+        let Some(signal) = signal_plot.shared_signal() else {
+            return;
+        };
+        let range = signal_plot.selected_range();
+        let key = (Arc::as_ptr(&signal) as usize, range.clone());
+        if key == self.key || range.is_empty() {
+            return;
+        }
+        self.task = Some((
+            key,
+            thread::spawn(move || match signal.as_ref() {
+                Signal::Real(sig) => {
+                    let data = sig.get(range, 1);
+                    vec![("Real".to_owned(), compute_stats(data, |&x| x))]
+                }
+                Signal::Complex(sig) => {
+                    let data = sig.get(range, 1);
+                    vec![
+                        ("I".to_owned(), compute_stats(data, |x| x.re)),
+                        ("Q".to_owned(), compute_stats(data, |x| x.im)),
+                        ("Magnitude".to_owned(), compute_stats(data, |x| x.norm())),
+                    ]
+                }
+            }),
+        ));
+        ctx.request_repaint();
+    }
+}
@@ -15,13 +15,62 @@ pub fn human_readable_freq(freq: f64) -> String {
     let mut freq = freq;
     let mut units = vec!["GHz", "MHz", "kHz", "Hz"];
     let mut unit = units.pop().unwrap();
-    while freq >= 1_000.0 && !units.is_empty() {
+    while freq.abs() >= 1_000.0 && !units.is_empty() {
         freq /= 1_000.0;
         unit = units.pop().unwrap();
     }
     format!("{}{}", (freq * 1000000.).round() / 1000000., unit)
 }
 
+pub fn format_freq(f: f64) -> String {
+    if f.abs() < 1_000.0 {
+        return format!("{:.0} Hz", f);
+    }
+    if f.abs() < 1_000_000.0 {
+        return format!("{} kHz", f / 1_000.0);
+    }
+    if f.abs() < 1_000_000_000.0 {
+        return format!("{} MHz", f / 1_000_000.0);
+    }
+    format!("{} GHz", f / 1_000_000_000.0)
+}
+
+/// 把数字和单位拆开, 单位转为小写, 例如 "10 MHz" -> (10.0, "mhz")
+fn split_unit(str: &str) -> Option<(f64, String)> {
+    let mut str = str.trim().to_owned();
+    str.make_ascii_lowercase();
+    let num = str.trim_end_matches(|c: char| c.is_alphabetic() && c != 'e' && c != '.');
+    let unit = str.trim_start_matches(|c: char| {
+        c.is_numeric() || c == '.' || c == 'e' || c == '-' || c == '+'
+    });
+    let num = num.trim().parse::<f64>().ok()?;
+    Some((num, unit.trim().to_owned()))
+}
+
+pub fn parse_freq(str: &str) -> Option<f64> {
+    // str 1000hz 1000mhz 1000 MHz 10GHz 200 GhZ is valid
+    let (num, unit) = split_unit(str)?;
+    match unit.as_str() {
+        "" | "hz" => Some(num),
+        "khz" => Some(num * 1_000.0),
+        "mhz" => Some(num * 1_000_000.0),
+        "ghz" => Some(num * 1_000_000_000.0),
+        _ => None,
+    }
+}
+
+/// 解析时间, 单位可以是 s ms us ns, 没有单位时按秒处理. 返回秒
+pub fn parse_time(str: &str) -> Option<f64> {
+    let (num, unit) = split_unit(str)?;
+    match unit.as_str() {
+        "" | "s" => Some(num),
+        "ms" => Some(num * 1e-3),
+        "us" | "µs" => Some(num * 1e-6),
+        "ns" => Some(num * 1e-9),
+        _ => None,
+    }
+}
+
 pub fn guess_signal_type(filename: &str) -> Option<SignalType> {
     if filename.ends_with(".cf32") {
         Some(SignalType::Complex64)
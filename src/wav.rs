@@ -0,0 +1,32 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// 写入单声道 16 位 PCM 的 WAV 文件, 按峰值归一化到满幅
+pub fn write_wav(path: &str, samples: &[f32], sample_rate: u32) -> io::Result<()> {
+    let peak = samples.iter().fold(0f32, |peak, x| peak.max(x.abs()));
+    let scale = if peak > 0. {
+        i16::MAX as f32 / peak
+    } else {
+        0.
+    };
+    let data_len = (samples.len() * 2) as u32;
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(b"RIFF")?;
+    file.write_all(&(36 + data_len).to_le_bytes())?;
+    file.write_all(b"WAVE")?;
+    file.write_all(b"fmt ")?;
+    file.write_all(&16u32.to_le_bytes())?;
+    // PCM, 单声道
+    file.write_all(&1u16.to_le_bytes())?;
+    file.write_all(&1u16.to_le_bytes())?;
+    file.write_all(&sample_rate.to_le_bytes())?;
+    file.write_all(&(sample_rate * 2).to_le_bytes())?;
+    file.write_all(&2u16.to_le_bytes())?;
+    file.write_all(&16u16.to_le_bytes())?;
+    file.write_all(b"data")?;
+    file.write_all(&data_len.to_le_bytes())?;
+    for &x in samples {
+        file.write_all(&((x * scale).round() as i16).to_le_bytes())?;
+    }
+    file.flush()
+}